@@ -0,0 +1,318 @@
+use core::any::{Any, TypeId};
+use std::{collections::HashMap, sync::Arc};
+
+use state::{sync::MutexState, StateTrait};
+
+use crate::{Emitter, IntoListener, SyncDirect, Transport};
+
+/// Creates the transport backing each event type or topic of a [`Bus`].
+pub trait TransportFactory {
+    type Transport<E: Clone + Send + 'static>: Transport<'static, E> + Send + Sync + 'static;
+
+    fn create<E: Clone + Send + 'static>(&self) -> Self::Transport<E>;
+}
+
+pub type BusEmitter<E, F> = Emitter<E, <F as TransportFactory>::Transport<E>>;
+
+pub type BusSubscription<E, F> =
+    <<F as TransportFactory>::Transport<E> as Transport<'static, E>>::Subscription;
+
+pub type BusListener<E, F> =
+    <<F as TransportFactory>::Transport<E> as Transport<'static, E>>::Listener;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyncDirectFactory;
+
+impl TransportFactory for SyncDirectFactory {
+    type Transport<E: Clone + Send + 'static> = SyncDirect<E>;
+
+    fn create<E: Clone + Send + 'static>(&self) -> Self::Transport<E> {
+        SyncDirect::default()
+    }
+}
+
+#[cfg(feature = "thread")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadFactory;
+
+#[cfg(feature = "thread")]
+impl TransportFactory for ThreadFactory {
+    type Transport<E: Clone + Send + 'static> = crate::Thread<E>;
+
+    fn create<E: Clone + Send + 'static>(&self) -> Self::Transport<E> {
+        crate::Thread::new()
+    }
+}
+
+/// Creates [`Pool`](crate::Pool) transports which share a single executor.
+#[cfg(feature = "threadpool")]
+#[derive(Clone, Default)]
+pub struct PoolFactory<X = crate::pool::SyncThreadPool> {
+    pool: X,
+}
+
+#[cfg(feature = "threadpool")]
+impl PoolFactory {
+    pub fn new(pool: impl Into<crate::pool::SyncThreadPool>) -> PoolFactory {
+        PoolFactory::with_executor(pool.into())
+    }
+}
+
+#[cfg(feature = "threadpool")]
+impl<X> PoolFactory<X> {
+    pub fn with_executor(executor: X) -> PoolFactory<X> {
+        PoolFactory { pool: executor }
+    }
+}
+
+#[cfg(feature = "threadpool")]
+impl<X> TransportFactory for PoolFactory<X>
+where
+    X: crate::pool::Executor + Clone,
+{
+    type Transport<E: Clone + Send + 'static> = crate::Pool<E, X>;
+
+    fn create<E: Clone + Send + 'static>(&self) -> Self::Transport<E> {
+        crate::Pool::with_executor(self.pool.clone())
+    }
+}
+
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncFactory<X>(pub X);
+
+#[cfg(feature = "async")]
+impl<X> TransportFactory for AsyncFactory<X>
+where
+    X: crate::r#async::Executor + Clone + Send + Sync + 'static,
+{
+    type Transport<E: Clone + Send + 'static> = crate::Async<E, X>;
+
+    fn create<E: Clone + Send + 'static>(&self) -> Self::Transport<E> {
+        crate::Async::new(self.0.clone())
+    }
+}
+
+/// A dot separated topic pattern.
+///
+/// `*` matches exactly one segment and `#` matches any number of trailing segments,
+/// so `job.*.done` matches `job.1.done` and `job.#` matches both `job` and `job.1.done`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pattern: String,
+}
+
+impl Pattern {
+    pub fn new(pattern: impl Into<String>) -> Pattern {
+        Pattern {
+            pattern: pattern.into(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn matches(&self, topic: &str) -> bool {
+        let mut pattern = self.pattern.split('.');
+        let mut topic = topic.split('.');
+
+        loop {
+            match (pattern.next(), topic.next()) {
+                (Some("#"), _) => return pattern.next().is_none(),
+                (Some("*"), Some(_)) => continue,
+                (Some(p), Some(t)) if p == t => continue,
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(pattern: &str) -> Self {
+        Pattern::new(pattern)
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(pattern: String) -> Self {
+        Pattern::new(pattern)
+    }
+}
+
+type AnyEmitter = Arc<dyn Any + Send + Sync>;
+
+struct Topic {
+    pattern: Pattern,
+    event: TypeId,
+    emitter: AnyEmitter,
+}
+
+/// A registry of emitters keyed by event type or by topic pattern.
+pub struct Bus<F = SyncDirectFactory> {
+    factory: F,
+    events: MutexState<HashMap<TypeId, AnyEmitter>>,
+    topics: MutexState<Vec<Topic>>,
+}
+
+impl<F: Clone> Clone for Bus<F> {
+    fn clone(&self) -> Self {
+        Bus {
+            factory: self.factory.clone(),
+            events: self.events.clone(),
+            topics: self.topics.clone(),
+        }
+    }
+}
+
+impl<F: TransportFactory + Default> Default for Bus<F> {
+    fn default() -> Self {
+        Bus::new_with(F::default())
+    }
+}
+
+impl Bus {
+    pub fn new() -> Bus {
+        Bus::default()
+    }
+}
+
+impl<F> Bus<F>
+where
+    F: TransportFactory,
+{
+    pub fn new_with(factory: F) -> Bus<F> {
+        Bus {
+            factory,
+            events: MutexState::new(HashMap::default()),
+            topics: MutexState::new(Vec::default()),
+        }
+    }
+
+    /// The emitter for events of type `E`, created on first use.
+    pub fn emitter<E>(&self) -> Arc<BusEmitter<E, F>>
+    where
+        E: Clone + Send + 'static,
+    {
+        let emitter = self
+            .events
+            .write(|events| {
+                events
+                    .entry(TypeId::of::<E>())
+                    .or_insert_with(|| Arc::new(Emitter::new_with(self.factory.create::<E>())))
+                    .clone()
+            })
+            .expect("bus");
+
+        downcast::<E, F>(emitter)
+    }
+
+    pub fn listen<E, L>(&self, listener: L) -> BusSubscription<E, F>
+    where
+        E: Clone + Send + 'static,
+        L: IntoListener<'static, BusListener<E, F>, E>,
+    {
+        self.emitter::<E>().listen(listener)
+    }
+
+    pub fn emit<E>(&self, event: E)
+    where
+        E: Clone + Send + 'static,
+    {
+        let emitter = self
+            .events
+            .read(|events| events.get(&TypeId::of::<E>()).cloned())
+            .expect("bus");
+
+        if let Some(emitter) = emitter {
+            downcast::<E, F>(emitter).emit(event);
+        }
+    }
+
+    /// The emitter for events of type `E` published to topics matching `pattern`.
+    pub fn topic<E>(&self, pattern: impl Into<Pattern>) -> Arc<BusEmitter<E, F>>
+    where
+        E: Clone + Send + 'static,
+    {
+        let pattern = pattern.into();
+        let event = TypeId::of::<E>();
+
+        let emitter = self
+            .topics
+            .write(|topics| {
+                if let Some(topic) = topics
+                    .iter()
+                    .find(|topic| topic.event == event && topic.pattern == pattern)
+                {
+                    return topic.emitter.clone();
+                }
+
+                let emitter: AnyEmitter = Arc::new(Emitter::new_with(self.factory.create::<E>()));
+                topics.push(Topic {
+                    pattern,
+                    event,
+                    emitter: emitter.clone(),
+                });
+                emitter
+            })
+            .expect("bus");
+
+        downcast::<E, F>(emitter)
+    }
+
+    pub fn listen_topic<E, L>(
+        &self,
+        pattern: impl Into<Pattern>,
+        listener: L,
+    ) -> BusSubscription<E, F>
+    where
+        E: Clone + Send + 'static,
+        L: IntoListener<'static, BusListener<E, F>, E>,
+    {
+        self.topic::<E>(pattern).listen(listener)
+    }
+
+    /// Publish `event` to every pattern matching `topic`.
+    /// Returns the number of patterns which received the event.
+    pub fn emit_topic<E>(&self, topic: &str, event: E) -> usize
+    where
+        E: Clone + Send + 'static,
+    {
+        let event_type = TypeId::of::<E>();
+
+        // Collect the emitters first so listeners are free to use the bus
+        let mut emitters = self
+            .topics
+            .read(|topics| {
+                topics
+                    .iter()
+                    .filter(|next| next.event == event_type && next.pattern.matches(topic))
+                    .map(|next| next.emitter.clone())
+                    .collect::<Vec<_>>()
+            })
+            .expect("bus");
+
+        let count = emitters.len();
+
+        if let Some(last) = emitters.pop() {
+            for emitter in emitters {
+                downcast::<E, F>(emitter).emit(event.clone());
+            }
+            downcast::<E, F>(last).emit(event);
+        }
+
+        count
+    }
+}
+
+fn downcast<E, F>(emitter: AnyEmitter) -> Arc<BusEmitter<E, F>>
+where
+    E: Clone + Send + 'static,
+    F: TransportFactory,
+{
+    match emitter.downcast() {
+        Ok(emitter) => emitter,
+        Err(_) => unreachable!("emitter registered under the wrong type"),
+    }
+}
@@ -1,6 +1,11 @@
-use core::{fmt, marker::PhantomData};
+use core::{fmt, marker::PhantomData, time::Duration};
 
-use crate::{Direct, IntoListener, Transport};
+use state::{Downgrade, StateTrait};
+
+use crate::{
+    Direct, IntoListener, ListenerPanic, ListenerStats, PanicHandler, PanicPolicy, Transport,
+    TrySend, TrySendError, WeakListener,
+};
 
 pub struct Emitter<E, T> {
     trigger: T,
@@ -48,16 +53,87 @@ where
         }
     }
 
+    pub fn transport(&self) -> &T {
+        &self.trigger
+    }
+
     pub fn emit(&self, event: E) {
         self.trigger.trigger(event);
     }
 
+    pub fn try_emit(&self, event: E) -> Result<TrySend<E>, TrySendError<E>> {
+        self.trigger.try_trigger(event)
+    }
+
+    pub fn set_panic_handler(&self, handler: PanicHandler) {
+        self.trigger.set_panic_handler(handler)
+    }
+
+    /// Report panicking listeners to `hook` and keep or remove them according to `policy`.
+    pub fn on_panic<F>(&self, policy: PanicPolicy, hook: F)
+    where
+        F: Fn(&ListenerPanic) + Send + Sync + 'static,
+    {
+        self.set_panic_handler(PanicHandler::with_hook(policy, hook))
+    }
+
+    pub fn listener_count(&self) -> usize {
+        self.trigger.listener_count()
+    }
+
+    pub fn pending(&self) -> usize {
+        self.trigger.pending()
+    }
+
+    pub fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.trigger.listener_stats()
+    }
+
+    pub fn flush(&self) {
+        self.trigger.flush()
+    }
+
+    pub fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.trigger.flush_timeout(timeout)
+    }
+
+    pub fn shutdown(&self) {
+        self.trigger.shutdown()
+    }
+
+    pub fn shutdown_timeout(&self, timeout: Duration) -> bool {
+        self.trigger.shutdown_timeout(timeout)
+    }
+
     pub fn listen<L>(&self, listener: L) -> T::Subscription
     where
         L: IntoListener<'a, T::Listener, E>,
     {
         self.trigger.create_listener(listener)
     }
+
+    /// Listen with mutable access to `state`, without keeping it alive.
+    /// The listener is removed once the state has been dropped.
+    pub fn listen_weak<S, U, F>(&self, state: &S, func: F) -> T::Subscription
+    where
+        S: Downgrade,
+        S::Output: StateTrait<U>,
+        WeakListener<S::Output, U, F>: IntoListener<'a, T::Listener, E>,
+    {
+        self.listen(WeakListener {
+            state: state.downgrade(),
+            func,
+            _t: PhantomData,
+        })
+    }
+
+    pub fn listen_with_priority<L>(&self, priority: i32, listener: L) -> T::Subscription
+    where
+        L: IntoListener<'a, T::Listener, E>,
+    {
+        self.trigger
+            .create_listener_with_priority(listener, priority)
+    }
 }
 
 impl<'a, E, T> Emitter<E, T>
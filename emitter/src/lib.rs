@@ -2,6 +2,19 @@ mod emitter;
 mod transport;
 pub use self::{emitter::*, transport::*};
 
+pub mod request;
+pub use request::{Replies, Request, Responder};
+
+#[cfg(feature = "sync")]
+pub mod bus;
+#[cfg(feature = "sync")]
+pub use bus::Bus;
+
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "stream")]
+pub use stream::EventStream;
+
 #[cfg(test)]
 mod test {
 
@@ -38,6 +51,364 @@ mod test {
         println!("GLOBAL {}", global.0);
     }
 
+    #[test]
+    fn test_unsubscribe() {
+        let mut seen = Vec::new();
+        let emitter = Emitter::<i32, Direct<i32>>::new();
+
+        let once = emitter.listen(|event: &i32| {
+            seen.push(*event);
+            false
+        });
+
+        let until = emitter.listen(|event: &i32| if *event < 2 { Ok(()) } else { Err(()) });
+
+        emitter.emit(1);
+        assert!(once.is_closed());
+        assert!(!until.is_closed());
+
+        emitter.emit(2);
+        emitter.emit(3);
+        assert!(until.is_closed());
+
+        drop((once, until, emitter));
+        assert_eq!(seen, vec![1]);
+    }
+
+    #[test]
+    fn test_priority() {
+        let order = std::cell::RefCell::new(Vec::new());
+        let emitter = Emitter::<i32, Direct<i32>>::new();
+
+        let _a = emitter.listen(|_: &i32| order.borrow_mut().push("a"));
+        let _b = emitter.listen_with_priority(10, |_: &i32| order.borrow_mut().push("audit"));
+        let _c = emitter.listen(|event: &i32| {
+            order.borrow_mut().push("c");
+            if *event > 1 {
+                Flow::Stop
+            } else {
+                Flow::Continue
+            }
+        });
+        let _d = emitter.listen_with_priority(-1, |_: &i32| order.borrow_mut().push("d"));
+
+        emitter.emit(1);
+        assert_eq!(*order.borrow(), vec!["audit", "a", "c", "d"]);
+
+        order.borrow_mut().clear();
+        emitter.emit(2);
+        assert_eq!(*order.borrow(), vec!["audit", "a", "c"]);
+    }
+
+    #[test]
+    fn test_reentrant() {
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let emitter = Emitter::<i32, Direct<i32>>::new();
+
+        let inner = emitter.clone();
+        let log = seen.clone();
+        let _a = emitter.listen(move |event: &i32| {
+            log.borrow_mut().push(format!("a:{}", event));
+            if *event == 1 {
+                inner.emit(2);
+                let log = log.clone();
+                inner
+                    .listen(move |event: &i32| log.borrow_mut().push(format!("late:{}", event)))
+                    .detach();
+            }
+        });
+
+        let log = seen.clone();
+        let _b = emitter.listen(move |event: &i32| log.borrow_mut().push(format!("b:{}", event)));
+
+        emitter.emit(1);
+
+        assert_eq!(*seen.borrow(), vec!["a:1", "b:1", "a:2", "b:2", "late:2"]);
+    }
+
+    #[test]
+    fn test_stats() {
+        let emitter = Emitter::<i32, Direct<i32>>::new();
+        let pending = std::rc::Rc::new(std::cell::Cell::new(0));
+
+        let inner = emitter.clone();
+        let _a = emitter.listen_with_priority(1, move |event: &i32| {
+            if *event == 1 {
+                inner.emit(2);
+            }
+        });
+        let (inner, seen) = (emitter.clone(), pending.clone());
+        let _b = emitter.listen(move |event: &i32| {
+            if *event == 1 {
+                seen.set(inner.pending());
+            }
+        });
+        let once = emitter.listen(|_: &i32| false);
+        assert_eq!(emitter.listener_count(), 3);
+
+        emitter.emit(1);
+        assert_eq!(pending.get(), 1);
+        assert_eq!(emitter.pending(), 0);
+        assert_eq!(emitter.listener_count(), 2);
+        assert!(once.is_closed());
+
+        let stats = emitter.listener_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].priority, stats[0].calls), (1, 2));
+        assert_eq!((stats[1].priority, stats[1].calls), (0, 2));
+        assert!(stats[0].max <= stats[0].total);
+
+        let many = ListenerStats {
+            calls: 1 << 32,
+            total: std::time::Duration::from_secs(1 << 32),
+            ..stats[0]
+        };
+        assert_eq!(many.mean(), std::time::Duration::from_secs(1));
+        assert_eq!(ListenerStats::default().mean(), std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn test_subscription_set() {
+        let emitter = Emitter::<i32, Direct<i32>>::new();
+        let other = Emitter::<&str, Direct<&str>>::new();
+
+        let mut set = SubscriptionSet::new();
+        set.insert(emitter.listen(|_: &i32| {}));
+        set.insert(emitter.listen(|_: &i32| false));
+        set.insert(other.listen(|_: &&str| {}));
+        assert_eq!(set.len(), 3);
+
+        emitter.emit(1);
+        set.prune();
+        assert_eq!(set.len(), 2);
+
+        drop(set);
+        assert_eq!(emitter.listener_count() + other.listener_count(), 0);
+
+        let set = (0..2)
+            .map(|_| emitter.listen(|_: &i32| {}))
+            .collect::<SubscriptionSet>();
+        set.detach_all();
+        assert_eq!(emitter.listener_count(), 2);
+
+        let mut calls = 0;
+        let owner = state::State::new("component");
+        let owned = emitter.listen(CallbackMutExt::owned_by(|_: &i32| calls += 1, &owner));
+        emitter.emit(2);
+        drop(owner);
+        emitter.emit(3);
+        assert!(owned.is_closed());
+        drop((owned, emitter));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_combinators() {
+        use std::time::Duration;
+
+        let seen = std::cell::RefCell::new(Vec::new());
+        let timer = ManualTimer::new();
+        let emitter = Emitter::<i32, Direct<i32>>::new();
+
+        let even = emitter.listen(CallbackMutExt::filter(
+            |event: &i32| seen.borrow_mut().push(format!("even:{}", event)),
+            |event: &i32| event % 2 == 0,
+        ));
+        let half = emitter.listen(CallbackMutExt::filter_map(
+            |event: &String| seen.borrow_mut().push(event.clone()),
+            |event: &i32| (event % 2 == 0).then(|| format!("half:{}", event / 2)),
+        ));
+        let first = emitter.listen(CallbackMutExt::take(
+            |event: &i32| seen.borrow_mut().push(format!("take:{}", event)),
+            2,
+        ));
+        let throttled = emitter.listen(CallbackMutExt::throttle(
+            |event: &i32| seen.borrow_mut().push(format!("throttle:{}", event)),
+            Duration::from_secs(60),
+        ));
+        let debounced = emitter.listen(CallbackMutExt::debounce(
+            |event: &i32| seen.borrow_mut().push(format!("debounce:{}", event)),
+            Duration::from_millis(50),
+            timer.clone(),
+        ));
+
+        for event in 1..=4 {
+            emitter.emit(event);
+        }
+        assert!(first.is_closed());
+
+        // Only the call scheduled by the last event goes through
+        assert_eq!(timer.advance(Duration::from_millis(49)), 0);
+        assert_eq!(timer.advance(Duration::from_millis(1)), 4);
+        assert_eq!(timer.pending(), 0);
+
+        // Still pending when the listener is dropped
+        emitter.emit(5);
+        drop((even, half, first, throttled, debounced, emitter));
+        assert_eq!(timer.advance(Duration::from_millis(50)), 1);
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                "take:1",
+                "throttle:1",
+                "even:2",
+                "half:1",
+                "take:2",
+                "even:4",
+                "half:2",
+                "debounce:4",
+                "debounce:5"
+            ]
+        );
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_debounce_thread() {
+        use std::{sync::mpsc, time::Duration};
+
+        let timer = ThreadTimer::new();
+        let emitter = Emitter::<i32, SyncDirect<i32>>::new();
+        let (tx, rx) = mpsc::channel();
+        let tx = std::sync::Mutex::new(tx);
+
+        let _debounced = emitter.listen(CallbackExt::debounce(
+            move |event: &i32| tx.lock().unwrap().send(*event).unwrap(),
+            Duration::from_millis(50),
+            timer,
+        ));
+
+        emitter.emit(1);
+        emitter.emit(2);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(2));
+        emitter.emit(3);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(3));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_request() {
+        let emitter = Emitter::<Request<&str, Option<&str>>, Direct<_>>::new();
+
+        let _json = emitter.respond(|path: &&str| path.ends_with(".json").then_some("json"));
+        let _any = emitter.respond(|_: &&str| Some("binary"));
+        let _custom = emitter.listen(|request: &Request<&str, Option<&str>>| {
+            if request.event().starts_with("custom:") {
+                request.reply(Some("custom"));
+            }
+        });
+
+        let replies = emitter.request("data.json");
+        assert!(replies.is_done());
+        assert_eq!(replies.wait(), vec![Some("json"), Some("binary")]);
+
+        let handlers = emitter
+            .request("custom:data")
+            .fold(0, |count, reply| count + reply.is_some() as usize);
+        assert_eq!(handlers, 2);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_request_kept() {
+        use std::sync::{Arc, Mutex};
+
+        // The transport and the listeners keep copies of the request around
+        let emitter = Emitter::new_with(Replay::new(SyncDirect::<Request<i32, i32>>::default(), 4));
+        let kept = Arc::new(Mutex::new(Vec::new()));
+        let _double = emitter.respond(|event: &i32| event * 2);
+        let _keep = emitter.listen({
+            let kept = kept.clone();
+            move |request: &Request<i32, i32>| kept.lock().unwrap().push(request.clone())
+        });
+
+        let replies = emitter.request(3);
+        assert!(replies.is_done());
+        let replies = replies.wait_timeout(std::time::Duration::from_millis(300));
+        assert_eq!(replies.ok(), Some(vec![6]));
+
+        // Late replies are ignored
+        let replies = emitter.request(4);
+        kept.lock().unwrap()[1].reply(0);
+        let _late = emitter.listen_with_replay(|request: &Request<i32, i32>| request.reply(1));
+        assert_eq!(replies.wait(), vec![8]);
+    }
+
+    #[cfg(feature = "thread")]
+    #[test]
+    fn test_thread_request() {
+        let emitter = Emitter::<Request<i32, i32>, Thread<_>>::new();
+
+        let _double = emitter.respond(|event: &i32| event * 2);
+        let _square = emitter.respond(|event: &i32| event * event);
+
+        let replies = emitter.request(3);
+        assert_eq!(replies.wait(), vec![6, 9]);
+
+        let replies = emitter.request(4);
+        let replies = replies.wait_timeout(std::time::Duration::from_secs(5));
+        assert_eq!(replies.ok(), Some(vec![8, 16]));
+    }
+
+    #[test]
+    fn test_manual() {
+        let emitter = Emitter::<i32, Manual<i32>>::new();
+
+        let relay = emitter.clone();
+        let _first = emitter.listen(move |event: &i32| {
+            if *event == 1 {
+                relay.emit(10);
+            }
+        });
+        let _second = emitter.listen(|event: &i32| *event < 10);
+
+        emitter.emit(1);
+        emitter.emit(2);
+        assert_eq!(emitter.pending(), 2);
+        assert!(emitter.transport().dispatches().is_empty());
+
+        assert!(emitter.transport().step());
+        assert_eq!(emitter.transport().events(), vec![1, 2, 10]);
+        assert_eq!(emitter.transport().run_until_idle(), 2);
+        assert!(!emitter.transport().step());
+
+        let dispatches = emitter.transport().dispatches();
+        assert_eq!(dispatches.len(), 6);
+        assert_eq!(
+            dispatches[5],
+            Dispatched {
+                event: 2,
+                listener: 1,
+                flow: Flow::Unsubscribe
+            }
+        );
+        assert_eq!(emitter.listener_count(), 1);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_sync_reentrant() {
+        use std::sync::{Arc, Mutex};
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let emitter = Emitter::<i32, SyncDirect<i32>>::new();
+
+        let inner = emitter.clone();
+        let log = seen.clone();
+        emitter
+            .listen(move |event: &i32| {
+                log.lock().unwrap().push(*event);
+                if *event < 3 {
+                    inner.emit(event + 1);
+                }
+            })
+            .detach();
+
+        emitter.emit(1);
+
+        assert_eq!(*seen.lock().unwrap(), vec![1, 2, 3]);
+    }
+
     #[cfg(feature = "sync")]
     #[test]
     fn test_sync() {
@@ -58,12 +429,335 @@ mod test {
         drop(emitter);
     }
 
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_listen_weak() {
+        use state::StateTrait;
+
+        let emitter = Emitter::<i32, SyncDirect<i32>>::new();
+        let total = state::MutexState::new(0);
+
+        let sub = emitter.listen_weak(&total, |total: &mut i32, event: &i32| *total += event);
+
+        emitter.emit(1);
+        emitter.emit(2);
+        assert_eq!(total.read(|total| *total).unwrap(), 3);
+
+        drop(total);
+        emitter.emit(3);
+        assert!(sub.is_closed());
+        assert_eq!(emitter.listener_count(), 0);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_replay() {
+        use std::sync::{Arc, Mutex};
+
+        let emitter = Emitter::new_with(Replay::new(SyncDirect::<i32>::default(), 2));
+        for event in 1..=3 {
+            emitter.emit(event);
+        }
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let _late = emitter.listen_with_replay(move |event: &i32| log.lock().unwrap().push(*event));
+        let plain = Arc::new(Mutex::new(Vec::new()));
+        let log = plain.clone();
+        let _plain = emitter.listen(move |event: &i32| log.lock().unwrap().push(*event));
+
+        emitter.emit(4);
+        assert_eq!(*seen.lock().unwrap(), vec![2, 3, 4]);
+        assert_eq!(*plain.lock().unwrap(), vec![4]);
+
+        let mut status = Vec::new();
+        let latest = Emitter::new_with(Replay::latest(Direct::<&'static str>::default()));
+        latest.emit("connecting");
+        latest.emit("online");
+
+        let once = latest.listen_with_replay(|event: &&'static str| {
+            status.push(*event);
+            false
+        });
+        latest.emit("offline");
+        assert!(once.is_closed());
+        drop((once, latest));
+        assert_eq!(status, vec!["online"]);
+    }
+
+    #[cfg(feature = "thread")]
+    #[test]
+    fn test_replay_dropped() {
+        use std::sync::{mpsc, Arc, Mutex};
+
+        let emitter = Emitter::new_with(Replay::new(
+            Thread::<i32>::bounded(1, Overflow::DropNewest),
+            4,
+        ));
+
+        let (block_sx, block_rx) = mpsc::channel::<()>();
+        let block_rx = Mutex::new(block_rx);
+        let _sub = emitter.listen(move |_: &i32| {
+            block_rx.lock().unwrap().recv().ok();
+        });
+
+        emitter.emit(1);
+        // Wait for the worker to pick up the first event
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        emitter.emit(2);
+        emitter.emit(3);
+        assert_eq!(emitter.try_emit(4), Ok(TrySend::Dropped(4)));
+
+        block_sx.send(()).unwrap();
+        block_sx.send(()).unwrap();
+        emitter.flush();
+        assert_eq!(emitter.transport().events(), vec![1, 2]);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let _late = emitter.listen_with_replay(move |event: &i32| log.lock().unwrap().push(*event));
+        assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+
+        // Evicted events are forgotten, like dropped ones
+        let emitter = Emitter::new_with(Replay::new(
+            Thread::<i32>::bounded(2, Overflow::DropOldest),
+            4,
+        ));
+        let (block_sx, block_rx) = mpsc::channel::<()>();
+        let block_rx = Mutex::new(block_rx);
+        let _sub = emitter.listen(move |_: &i32| {
+            block_rx.lock().unwrap().recv().ok();
+        });
+
+        emitter.emit(1);
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        emitter.emit(2);
+        emitter.emit(3);
+        assert_eq!(emitter.try_emit(4), Ok(TrySend::Evicted(2)));
+        assert_eq!(emitter.try_emit(5), Ok(TrySend::Evicted(3)));
+        assert_eq!(emitter.transport().events(), vec![1, 4, 5]);
+
+        for _ in 0..3 {
+            block_sx.send(()).unwrap();
+        }
+        emitter.flush();
+        assert_eq!(emitter.transport().events(), vec![1, 4, 5]);
+
+        // Events emitted by listeners are remembered after the one being dispatched
+        let nested = Emitter::new_with(Replay::new(SyncDirect::<i32>::default(), 4));
+        let inner = nested.clone();
+        let _echo = nested.listen(move |event: &i32| {
+            if *event < 10 {
+                inner.emit(event + 10);
+            }
+        });
+        nested.emit(1);
+        assert_eq!(nested.transport().events(), vec![1, 11]);
+    }
+
+    #[cfg(all(unix, feature = "json"))]
+    #[test]
+    fn test_unix() {
+        use std::{
+            sync::{Arc, Mutex},
+            time::{Duration, Instant},
+        };
+        use transport::unix::Json;
+
+        let wait = |done: &dyn Fn() -> bool| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !done() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        };
+
+        let path = std::env::temp_dir().join(format!("emitter-{}.sock", std::process::id()));
+        let server = Emitter::new_with(Unix::<String, _>::bind(&path, Json).unwrap());
+        let local = Arc::new(Mutex::new(Vec::new()));
+        let log = local.clone();
+        let _local = server.listen(move |event: &String| log.lock().unwrap().push(event.clone()));
+
+        let client = Emitter::<String, SyncDirect<String>>::new();
+        let remote = Arc::new(Mutex::new(Vec::new()));
+        let log = remote.clone();
+        let _remote = client.listen(move |event: &String| log.lock().unwrap().push(event.clone()));
+
+        let connection = client.connect_unix(&path, Json).unwrap();
+        wait(&|| server.transport().clients() == 1);
+
+        server.emit("hello".to_string());
+        server.emit("world".to_string());
+        wait(&|| remote.lock().unwrap().len() == 2);
+
+        assert_eq!(*local.lock().unwrap(), vec!["hello", "world"]);
+        assert_eq!(*remote.lock().unwrap(), vec!["hello", "world"]);
+
+        server.shutdown();
+        wait(&|| connection.is_closed());
+        assert!(connection.is_closed());
+        assert!(!path.exists());
+    }
+
+    #[cfg(all(unix, feature = "json"))]
+    #[test]
+    fn test_unix_frame_limit() {
+        use std::{
+            io::Write,
+            os::unix::net::UnixListener,
+            sync::{Arc, Mutex},
+            time::{Duration, Instant},
+        };
+        use transport::unix::{Json, Limited};
+
+        let wait = |done: &dyn Fn() -> bool| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !done() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        };
+
+        // Events over the limit are only dispatched locally
+        let path = std::env::temp_dir().join(format!("emitter-limit-{}.sock", std::process::id()));
+        let server =
+            Emitter::new_with(Unix::<String, _>::bind(&path, Limited::new(Json, 8)).unwrap());
+        let client = Emitter::<String, SyncDirect<String>>::new();
+        let remote = Arc::new(Mutex::new(Vec::new()));
+        let log = remote.clone();
+        let _remote = client.listen(move |event: &String| log.lock().unwrap().push(event.clone()));
+
+        let _connection = client.connect_unix(&path, Json).unwrap();
+        wait(&|| server.transport().clients() == 1);
+
+        server.emit("too long to send".to_string());
+        server.emit("short".to_string());
+        wait(&|| !remote.lock().unwrap().is_empty());
+        assert_eq!(*remote.lock().unwrap(), vec!["short"]);
+        server.shutdown();
+
+        // A peer announcing a huge frame is disconnected instead of being trusted
+        let path = std::env::temp_dir().join(format!("emitter-huge-{}.sock", std::process::id()));
+        let listener = UnixListener::bind(&path).unwrap();
+        let connection = client.connect_unix(&path, Json).unwrap();
+        let (mut peer, _) = listener.accept().unwrap();
+        peer.write_all(&u32::MAX.to_be_bytes()).unwrap();
+
+        wait(&|| connection.is_closed());
+        assert!(connection.is_closed());
+        drop(peer);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(all(unix, feature = "json"))]
+    #[test]
+    fn test_unix_stalled() {
+        use std::{
+            os::unix::net::UnixStream,
+            time::{Duration, Instant},
+        };
+        use transport::unix::Json;
+
+        let path =
+            std::env::temp_dir().join(format!("emitter-stalled-{}.sock", std::process::id()));
+        let server = Emitter::new_with(
+            Unix::<String, _>::bind(&path, Json)
+                .unwrap()
+                .with_write_timeout(Some(Duration::from_millis(100))),
+        );
+
+        // Connected, but never reading
+        let _stalled = UnixStream::connect(&path).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.transport().clients() == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(server.transport().clients(), 1);
+
+        let event = "x".repeat(64 * 1024);
+        let start = Instant::now();
+        while server.transport().clients() > 0 && start.elapsed() < Duration::from_secs(5) {
+            server.emit(event.clone());
+        }
+        assert_eq!(server.transport().clients(), 0);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        server.shutdown();
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_panic() {
+        use std::sync::{Arc, Mutex};
+
+        let emitter = Emitter::<i32, SyncDirect<i32>>::new();
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let hook = reported.clone();
+        emitter.on_panic(PanicPolicy::Remove, move |panic: &ListenerPanic| {
+            hook.lock()
+                .unwrap()
+                .push((panic.message().map(String::from), panic.removed()));
+        });
+
+        let panicking = emitter.listen(|_: &i32| -> () { panic!("listener failed") });
+        let rx = received.clone();
+        let _ok = emitter.listen(move |event: &i32| rx.lock().unwrap().push(*event));
+
+        emitter.emit(1);
+        emitter.emit(2);
+
+        assert!(panicking.is_closed());
+        assert_eq!(*received.lock().unwrap(), vec![1, 2]);
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![(Some("listener failed".to_string()), true)]
+        );
+    }
+
+    #[test]
+    fn test_panic_unhandled() {
+        use std::{
+            cell::RefCell,
+            panic::{catch_unwind, AssertUnwindSafe},
+            rc::Rc,
+        };
+
+        let emitter = Emitter::<i32, Direct<i32>>::new();
+        let received = Rc::new(RefCell::new(Vec::new()));
+
+        let inner = emitter.clone();
+        let _panicking = emitter.listen(move |event: &i32| {
+            if *event == 1 {
+                inner.emit(10);
+                panic!("listener failed");
+            }
+        });
+        let rx = received.clone();
+        let _ok = emitter.listen(move |event: &i32| rx.borrow_mut().push(*event));
+
+        // Without a hook the panic reaches the caller, once every queued event was delivered
+        let err = catch_unwind(AssertUnwindSafe(|| emitter.emit(1))).unwrap_err();
+        assert_eq!(err.downcast_ref::<&str>(), Some(&"listener failed"));
+        assert_eq!(*received.borrow(), vec![1, 10]);
+
+        emitter.emit(2);
+        assert_eq!(*received.borrow(), vec![1, 10, 2]);
+
+        // A panicking hook doesn't leave the emitter stuck either
+        emitter.on_panic(PanicPolicy::Keep, |_: &ListenerPanic| panic!("hook failed"));
+        let err = catch_unwind(AssertUnwindSafe(|| emitter.emit(1))).unwrap_err();
+        assert_eq!(err.downcast_ref::<&str>(), Some(&"hook failed"));
+
+        emitter.emit(3);
+        assert_eq!(*received.borrow(), vec![1, 10, 2, 1, 10, 3]);
+    }
+
     #[cfg(feature = "thread")]
     #[test]
     fn test_thread() {
         let emitter = Emitter::<i32, Thread<i32>>::new();
 
-        let sub = emitter.listen(|event: &i32| {
+        let _sub = emitter.listen(|event: &i32| {
             println!("THREADED GOT EVENT: {}", event);
         });
 
@@ -76,16 +770,40 @@ mod test {
 
         emitter.emit(201);
 
-        std::thread::sleep(std::time::Duration::from_secs(1));
+        emitter.shutdown();
         drop(emitter);
     }
 
-    #[cfg(feature = "pool")]
+    #[cfg(feature = "thread")]
+    #[test]
+    fn test_thread_stats() {
+        let emitter = Emitter::<i32, Thread<i32>>::new();
+        let (sx, rx) = crossbeam_channel::bounded::<()>(0);
+
+        let _block = emitter.listen(move |_: &i32| {
+            rx.recv().ok();
+        });
+
+        for event in 0..3 {
+            emitter.emit(event);
+        }
+        assert!(emitter.pending() >= 2);
+
+        for _ in 0..3 {
+            sx.send(()).unwrap();
+        }
+        emitter.flush();
+        assert_eq!(emitter.pending(), 0);
+        assert_eq!(emitter.listener_count(), 1);
+        assert_eq!(emitter.listener_stats()[0].calls, 3);
+    }
+
+    #[cfg(feature = "threadpool")]
     #[test]
     fn test_pool() {
         let emitter = Emitter::<i32, Pool<i32>>::new();
 
-        let sub = emitter.listen(|event: &i32| {
+        let _sub = emitter.listen(|event: &i32| {
             println!("PPOL GOT EVENT: {}", event);
         });
 
@@ -96,7 +814,317 @@ mod test {
 
         emitter.emit(201);
 
-        std::thread::sleep(std::time::Duration::from_millis(200));
+        emitter.flush();
         drop(emitter);
     }
+
+    #[cfg(feature = "threadpool")]
+    #[test]
+    fn test_pool_fan_out() {
+        use std::{
+            sync::{mpsc, Arc, Mutex},
+            time::Duration,
+        };
+
+        let emitter =
+            Emitter::<i32, FanOutPool<i32>>::new_with(FanOutPool::new(4, FanOut::Unordered));
+
+        let (sx, rx) = mpsc::channel();
+        let slow = Mutex::new(sx.clone());
+        let fast = Mutex::new(sx);
+        let _slow = emitter.listen(move |event: &i32| {
+            std::thread::sleep(Duration::from_millis(200));
+            slow.lock().unwrap().send(("slow", *event)).ok();
+        });
+        let _fast = emitter.listen(move |event: &i32| {
+            fast.lock().unwrap().send(("fast", *event)).ok();
+        });
+
+        emitter.emit(1);
+        assert_eq!(rx.recv_timeout(Duration::from_millis(100)), Ok(("fast", 1)));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(("slow", 1)));
+        emitter.flush();
+
+        let emitter = Emitter::<i32, FanOutPool<i32>>::new_with(FanOutPool::new(4, FanOut::Fifo));
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let _sub = emitter.listen({
+            let seen = seen.clone();
+            move |event: &i32| {
+                std::thread::sleep(Duration::from_millis(1));
+                seen.lock().unwrap().push(*event);
+            }
+        });
+
+        for i in 0..20 {
+            emitter.emit(i);
+        }
+        emitter.flush();
+        assert_eq!(*seen.lock().unwrap(), (0..20).collect::<Vec<_>>());
+        assert_eq!(emitter.listener_count(), 1);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_pool_rayon() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let count = std::sync::Arc::new(AtomicUsize::new(0));
+        let emitter =
+            Emitter::<i32, Pool<i32, Rayon>>::new_with(Pool::with_executor(Rayon::new(2).unwrap()));
+
+        let _sub = emitter.listen({
+            let count = count.clone();
+            move |event: &i32| {
+                count.fetch_add(*event as usize, Ordering::SeqCst);
+            }
+        });
+
+        for i in 0..100 {
+            emitter.emit(i);
+        }
+        emitter.flush();
+        assert_eq!(count.load(Ordering::SeqCst), 4950);
+
+        // Rayon aborts on panicking jobs, so unhandled listener panics stay on the worker
+        let _panicking = emitter.listen(|_: &i32| -> () { panic!("listener failed") });
+        emitter.emit(1);
+        emitter.emit(2);
+        emitter.flush();
+        assert_eq!(count.load(Ordering::SeqCst), 4953);
+
+        emitter.shutdown();
+        assert!(emitter.try_emit(1).is_err());
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_local_async() {
+        use futures::executor::{block_on, LocalPool};
+        use std::{cell::RefCell, rc::Rc};
+
+        let mut pool = LocalPool::new();
+        let emitter = Emitter::<i32, LocalAsync<i32, _>>::new_with(LocalAsync::new(pool.spawner()));
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let _sub = emitter.listen({
+            let seen = seen.clone();
+            move |event: i32| {
+                let seen = seen.clone();
+                async move {
+                    seen.borrow_mut().push(event);
+                    event < 3
+                }
+            }
+        });
+
+        emitter.emit(1);
+        assert!(seen.borrow().is_empty());
+        pool.run_until_stalled();
+        assert_eq!(*seen.borrow(), vec![1]);
+
+        block_on(emitter.emit_async(2));
+        assert_eq!(*seen.borrow(), vec![1, 2]);
+
+        block_on(emitter.emit_async(3));
+        assert_eq!(emitter.listener_count(), 0);
+        block_on(emitter.emit_async(4));
+        assert_eq!(*seen.borrow(), vec![1, 2, 3]);
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_async() {
+        let emitter = Emitter::<i32, Async<i32, r#async::Futures>>::new();
+
+        let (sx, rx) = std::sync::mpsc::channel();
+        let sx = std::sync::Mutex::new(sx);
+
+        let _sub = emitter.listen(move |event: i32| {
+            let sx = sx.lock().unwrap().clone();
+            async move {
+                sx.send(event).ok();
+            }
+        });
+
+        emitter.emit(200);
+
+        let timeout = std::time::Duration::from_secs(1);
+        assert_eq!(rx.recv_timeout(timeout), Ok(200));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_async_reentrant() {
+        use core::future::Future;
+        use std::sync::{Arc, Mutex};
+
+        // Runs the futures right away, on the emitting thread
+        #[derive(Clone, Copy, Default)]
+        struct Inline;
+
+        impl r#async::Executor for Inline {
+            fn spawn<F>(&self, future: F)
+            where
+                F: Future<Output = ()> + Send + 'static,
+            {
+                futures::executor::block_on(future);
+            }
+        }
+
+        let emitter = Emitter::<i32, Async<i32, Inline>>::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let inner = emitter.clone();
+        let log = seen.clone();
+        let _sub = emitter.listen(move |event: i32| {
+            log.lock().unwrap().push((event, inner.listener_count()));
+            if event == 1 {
+                inner.emit(2);
+            }
+            async move { event == 1 }
+        });
+
+        emitter.emit(1);
+        assert_eq!(*seen.lock().unwrap(), vec![(1, 1), (2, 1)]);
+        assert_eq!(emitter.listener_count(), 0);
+    }
+
+    #[cfg(feature = "thread")]
+    #[test]
+    fn test_thread_bounded() {
+        let emitter = Emitter::<i32, Thread<i32>>::new_with(Thread::bounded(1, Overflow::Error));
+
+        let (sx, rx) = std::sync::mpsc::channel();
+        let (block_sx, block_rx) = std::sync::mpsc::channel::<()>();
+        let block_rx = std::sync::Mutex::new(block_rx);
+
+        let _sub = emitter.listen(move |event: &i32| {
+            block_rx.lock().unwrap().recv().ok();
+            sx.send(*event).ok();
+        });
+
+        assert_eq!(emitter.try_emit(1), Ok(TrySend::Sent));
+        // Wait for the worker to pick up the first event
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(emitter.try_emit(2), Ok(TrySend::Sent));
+        assert_eq!(emitter.try_emit(3), Err(TrySendError::Full(3)));
+
+        block_sx.send(()).unwrap();
+        block_sx.send(()).unwrap();
+
+        let timeout = std::time::Duration::from_secs(1);
+        assert_eq!(rx.recv_timeout(timeout), Ok(1));
+        assert_eq!(rx.recv_timeout(timeout), Ok(2));
+    }
+
+    #[cfg(feature = "thread")]
+    #[test]
+    fn test_thread_shutdown() {
+        let emitter = Emitter::<i32, Thread<i32>>::new();
+
+        let (sx, rx) = std::sync::mpsc::channel();
+        let sx = std::sync::Mutex::new(sx);
+
+        let _sub = emitter.listen(move |event: &i32| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            sx.lock().unwrap().send(*event).ok();
+        });
+
+        for i in 0..5 {
+            emitter.emit(i);
+        }
+
+        assert!(emitter.shutdown_timeout(std::time::Duration::from_secs(5)));
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(emitter.try_emit(5), Err(TrySendError::Disconnected(5)));
+    }
+
+    #[cfg(feature = "thread")]
+    #[test]
+    fn test_thread_listen_order() {
+        let emitter = Emitter::<i32, Thread<i32>>::new();
+
+        let (block_sx, block_rx) = std::sync::mpsc::channel::<()>();
+        let block_rx = std::sync::Mutex::new(block_rx);
+        let _first = emitter.listen(move |event: &i32| {
+            if *event == 1 {
+                block_rx.lock().unwrap().recv().ok();
+            }
+        });
+
+        emitter.emit(1);
+        emitter.emit(2);
+
+        // Registered while 2 is still queued, so only sees the events after it
+        let (sx, rx) = std::sync::mpsc::channel();
+        let sx = std::sync::Mutex::new(sx);
+        let _second = emitter.listen(move |event: &i32| {
+            sx.lock().unwrap().send(*event).ok();
+        });
+
+        emitter.emit(3);
+        block_sx.send(()).unwrap();
+        emitter.flush();
+
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_bus() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Debug, PartialEq)]
+        struct Done(i32);
+
+        let bus = Bus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let typed = seen.clone();
+        let _a =
+            bus.listen(move |event: &i32| typed.lock().unwrap().push(format!("i32:{}", event)));
+
+        let wildcard = seen.clone();
+        let _b = bus.listen_topic("job.*.done", move |event: &Done| {
+            wildcard.lock().unwrap().push(format!("*:{}", event.0))
+        });
+
+        let exact = seen.clone();
+        let _c = bus.listen_topic("job.2.done", move |event: &Done| {
+            exact.lock().unwrap().push(format!("2:{}", event.0))
+        });
+
+        bus.emit(1);
+        assert_eq!(bus.emit_topic("job.1.done", Done(1)), 1);
+        assert_eq!(bus.emit_topic("job.2.done", Done(2)), 2);
+        assert_eq!(bus.emit_topic("job.2.failed", Done(3)), 0);
+
+        assert_eq!(*seen.lock().unwrap(), vec!["i32:1", "*:1", "*:2", "2:2"]);
+    }
+
+    #[cfg(all(feature = "stream", feature = "futures"))]
+    #[test]
+    fn test_stream() {
+        use futures::StreamExt;
+
+        let emitter = Emitter::<i32, SyncDirect<i32>>::new();
+        let mut stream = emitter.stream();
+        let mut lagging = emitter.stream_with(stream::Buffer::Lagging(2));
+
+        for i in 0..4 {
+            emitter.emit(i);
+        }
+
+        futures::executor::block_on(async {
+            assert_eq!(stream.next().await, Some(0));
+            assert_eq!(lagging.next().await, Some(2));
+            assert_eq!(lagging.next().await, Some(3));
+        });
+        assert_eq!(lagging.take_lagged(), 2);
+
+        drop(stream);
+        drop(lagging);
+        emitter.emit(4);
+    }
 }
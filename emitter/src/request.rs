@@ -0,0 +1,209 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Instant,
+};
+
+use crate::{Callback, CallbackMut, Emitter, Flow, IntoListener, Transport};
+
+struct State<R> {
+    replies: Vec<R>,
+    done: bool,
+    waker: Option<Waker>,
+}
+
+struct Shared<R> {
+    state: Mutex<State<R>>,
+    cvar: Condvar,
+}
+
+impl<R> Shared<R> {
+    fn lock(&self) -> MutexGuard<'_, State<R>> {
+        self.state.lock().expect("lock")
+    }
+}
+
+// Completes the request once the emitted request is dropped
+struct Completion<R>(Arc<Shared<R>>);
+
+impl<R> Drop for Completion<R> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.done = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        self.0.cvar.notify_all();
+    }
+}
+
+/// An event which listeners can reply to. Created by [`Emitter::request`].
+///
+/// The request completes when the emitted request is dropped, which transports do
+/// once they are done dispatching it. Copies made with `clone`, like the ones kept by
+/// `Replay` or an event stream, don't keep it pending,
+/// and their replies are ignored once it completed.
+///
+/// [`Manual`](crate::Manual) keeps every emitted event, so requests emitted
+/// on it never complete. Use [`Replies::wait_timeout`] with it.
+pub struct Request<E, R> {
+    event: E,
+    shared: Arc<Shared<R>>,
+    // Only held by the emitted request
+    _completion: Option<Completion<R>>,
+}
+
+impl<E: Clone, R> Clone for Request<E, R> {
+    fn clone(&self) -> Self {
+        Request {
+            event: self.event.clone(),
+            shared: self.shared.clone(),
+            _completion: None,
+        }
+    }
+}
+
+impl<E, R> Request<E, R> {
+    pub fn event(&self) -> &E {
+        &self.event
+    }
+
+    pub fn reply(&self, reply: R) {
+        let mut state = self.shared.lock();
+        if !state.done {
+            state.replies.push(reply);
+        }
+    }
+}
+
+/// The replies to a [`Request`].
+///
+/// Complete once the transport is done dispatching the request, which is
+/// already the case when `request` returns for transports dispatching in place.
+/// Can be waited on or awaited for threaded transports.
+pub struct Replies<R> {
+    shared: Arc<Shared<R>>,
+}
+
+impl<R> Replies<R> {
+    pub fn is_done(&self) -> bool {
+        self.shared.lock().done
+    }
+
+    /// Block until every listener has seen the request.
+    pub fn wait(self) -> Vec<R> {
+        let mut state = self.shared.lock();
+        while !state.done {
+            state = self.shared.cvar.wait(state).expect("lock");
+        }
+        core::mem::take(&mut state.replies)
+    }
+
+    /// Like [`wait`](Replies::wait), but gives up after `timeout`
+    /// and hands the pending replies back.
+    pub fn wait_timeout(self, timeout: Duration) -> Result<Vec<R>, Replies<R>> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        while !state.done {
+            let now = Instant::now();
+            if now >= deadline {
+                drop(state);
+                return Err(self);
+            }
+            state = self
+                .shared
+                .cvar
+                .wait_timeout(state, deadline - now)
+                .expect("lock")
+                .0;
+        }
+        Ok(core::mem::take(&mut state.replies))
+    }
+
+    /// Wait for the replies and reduce them in listener order.
+    pub fn fold<A, F>(self, init: A, func: F) -> A
+    where
+        F: FnMut(A, R) -> A,
+    {
+        self.wait().into_iter().fold(init, func)
+    }
+}
+
+impl<R> Future for Replies<R> {
+    type Output = Vec<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.lock();
+        if state.done {
+            Poll::Ready(core::mem::take(&mut state.replies))
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// A listener replying to requests with its return value.
+pub struct Responder<F>(pub F);
+
+impl<E, R, F> Callback<Request<E, R>> for Responder<F>
+where
+    F: Fn(&E) -> R,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &Request<E, R>) -> Self::Output {
+        input.reply((self.0)(&input.event));
+        Flow::Continue
+    }
+}
+
+impl<E, R, F> CallbackMut<Request<E, R>> for Responder<F>
+where
+    F: FnMut(&E) -> R,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &Request<E, R>) -> Self::Output {
+        input.reply((self.0)(&input.event));
+        Flow::Continue
+    }
+}
+
+impl<'a, E, R, T> Emitter<Request<E, R>, T>
+where
+    T: Transport<'a, Request<E, R>>,
+{
+    /// Emit `event` and collect the replies of the listeners.
+    pub fn request(&self, event: E) -> Replies<R> {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                replies: Vec::new(),
+                done: false,
+                waker: None,
+            }),
+            cvar: Condvar::new(),
+        });
+
+        self.emit(Request {
+            event,
+            shared: shared.clone(),
+            _completion: Some(Completion(shared.clone())),
+        });
+
+        Replies { shared }
+    }
+
+    /// Listen to requests, replying with the return value of `func`.
+    pub fn respond<F>(&self, func: F) -> T::Subscription
+    where
+        Responder<F>: IntoListener<'a, T::Listener, Request<E, R>>,
+    {
+        self.listen(Responder(func))
+    }
+}
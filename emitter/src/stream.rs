@@ -0,0 +1,179 @@
+use core::{
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak},
+};
+
+use futures_core::Stream;
+
+use crate::{Callback, CallbackMut, Emitter, IntoListener, Transport};
+
+/// How an [`EventStream`] buffers events which have not been polled yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Buffer {
+    #[default]
+    Unbounded,
+    /// Keep at most `n` events and drop new events while full.
+    DropNewest(usize),
+    /// Keep at most `n` events and drop the oldest one while full,
+    /// like a lagging receiver of a broadcast channel.
+    Lagging(usize),
+}
+
+struct Shared<E> {
+    queue: VecDeque<E>,
+    buffer: Buffer,
+    lagged: u64,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+impl<E> Shared<E> {
+    fn push(&mut self, event: E) {
+        match self.buffer {
+            Buffer::Unbounded => self.queue.push_back(event),
+            Buffer::DropNewest(cap) => {
+                if self.queue.len() < cap {
+                    self.queue.push_back(event);
+                } else {
+                    self.lagged += 1;
+                }
+            }
+            Buffer::Lagging(cap) => {
+                if cap == 0 {
+                    self.lagged += 1;
+                    return;
+                }
+                if self.queue.len() >= cap {
+                    self.queue.pop_front();
+                    self.lagged += 1;
+                }
+                self.queue.push_back(event);
+            }
+        }
+
+        self.wake();
+    }
+
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The listener half of an [`EventStream`].
+/// Unsubscribes itself once the stream is dropped.
+pub struct StreamSender<E> {
+    shared: Weak<Mutex<Shared<E>>>,
+}
+
+impl<E: Clone> StreamSender<E> {
+    fn send(&self, event: &E) -> bool {
+        match self.shared.upgrade() {
+            Some(shared) => {
+                shared.lock().expect("lock").push(event.clone());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<E: Clone> Callback<E> for StreamSender<E> {
+    type Output = bool;
+
+    fn call(&self, input: &E) -> Self::Output {
+        self.send(input)
+    }
+}
+
+impl<E: Clone> CallbackMut<E> for StreamSender<E> {
+    type Output = bool;
+
+    fn call_mut(&mut self, input: &E) -> Self::Output {
+        self.send(input)
+    }
+}
+
+impl<E> Drop for StreamSender<E> {
+    fn drop(&mut self) {
+        // The transport dropped the listener, so no more events will arrive
+        if let Some(shared) = self.shared.upgrade() {
+            let mut shared = shared.lock().expect("lock");
+            shared.closed = true;
+            shared.wake();
+        }
+    }
+}
+
+/// A stream of events backed by a listener. Dropping the stream drops the subscription.
+pub struct EventStream<E, S> {
+    shared: Arc<Mutex<Shared<E>>>,
+    _subscription: S,
+}
+
+impl<E, S> EventStream<E, S> {
+    /// The number of events dropped because the buffer was full, since the last call.
+    pub fn take_lagged(&self) -> u64 {
+        core::mem::take(&mut self.shared.lock().expect("lock").lagged)
+    }
+}
+
+impl<E, S> Stream for EventStream<E, S> {
+    type Item = E;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().expect("lock");
+
+        if let Some(event) = shared.queue.pop_front() {
+            Poll::Ready(Some(event))
+        } else if shared.closed {
+            Poll::Ready(None)
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.shared.lock().expect("lock").queue.len(), None)
+    }
+}
+
+impl<'a, E, T> Emitter<E, T>
+where
+    T: Transport<'a, E>,
+{
+    pub fn stream(&self) -> EventStream<E, T::Subscription>
+    where
+        StreamSender<E>: IntoListener<'a, T::Listener, E>,
+    {
+        self.stream_with(Buffer::Unbounded)
+    }
+
+    pub fn stream_with(&self, buffer: Buffer) -> EventStream<E, T::Subscription>
+    where
+        StreamSender<E>: IntoListener<'a, T::Listener, E>,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            queue: VecDeque::default(),
+            buffer,
+            lagged: 0,
+            closed: false,
+            waker: None,
+        }));
+
+        let subscription = self.listen(StreamSender {
+            shared: Arc::downgrade(&shared),
+        });
+
+        EventStream {
+            shared,
+            _subscription: subscription,
+        }
+    }
+}
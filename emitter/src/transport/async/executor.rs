@@ -0,0 +1,94 @@
+use core::future::Future;
+
+pub trait Executor {
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static;
+}
+
+impl<X> Executor for &X
+where
+    X: Executor,
+{
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        (**self).spawn(future)
+    }
+}
+
+impl<X> Executor for std::sync::Arc<X>
+where
+    X: Executor,
+{
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        (**self).spawn(future)
+    }
+}
+
+/// Spawns listener futures on the current tokio runtime.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tokio;
+
+#[cfg(feature = "tokio")]
+impl Executor for Tokio {
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        tokio::spawn(future);
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Executor for tokio::runtime::Handle {
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        tokio::runtime::Handle::spawn(self, future);
+    }
+}
+
+/// Runtime agnostic executor backed by `futures::executor::ThreadPool`.
+#[cfg(feature = "futures")]
+#[derive(Debug, Clone)]
+pub struct Futures {
+    pool: futures::executor::ThreadPool,
+}
+
+#[cfg(feature = "futures")]
+impl Futures {
+    pub fn new(pool: futures::executor::ThreadPool) -> Futures {
+        Futures { pool }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl Default for Futures {
+    fn default() -> Self {
+        Futures::new(futures::executor::ThreadPool::new().expect("thread pool"))
+    }
+}
+
+#[cfg(feature = "futures")]
+impl From<futures::executor::ThreadPool> for Futures {
+    fn from(pool: futures::executor::ThreadPool) -> Self {
+        Futures::new(pool)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl Executor for Futures {
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.pool.spawn_ok(future);
+    }
+}
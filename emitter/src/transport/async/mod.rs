@@ -1,3 +1,4 @@
-pub trait Executor {
-    fn spawn<F>(&self, future: F);
-}
+mod executor;
+mod transport;
+
+pub use self::{executor::*, transport::*};
@@ -0,0 +1,176 @@
+use core::{future::Future, pin::Pin};
+use std::sync::Arc;
+
+use generational_arena::{Arena, Index};
+use state::{sync, Downgrade, StateTrait};
+
+use super::Executor;
+use crate::{
+    transport::{func::Func, reply::Reply},
+    IntoListener, Listener, Subscription, Transport,
+};
+
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+pub struct AsyncListener<E> {
+    func: Box<dyn Func<E, Output = BoxFuture<bool>> + Send + Sync>,
+}
+
+impl<E> AsyncListener<E> {
+    pub fn new<F>(func: F) -> AsyncListener<E>
+    where
+        E: 'static,
+        F: Func<E> + Send + Sync + 'static,
+        F::Output: Future + Send + 'static,
+        <F::Output as Future>::Output: Reply,
+    {
+        AsyncListener {
+            func: Box::new(move |event: E| {
+                let future = func.call(event);
+                Box::pin(async move { future.await.carry_on() }) as BoxFuture<bool>
+            }),
+        }
+    }
+}
+
+impl<E> Listener<'static, E> for AsyncListener<E> {}
+
+impl<E> Func<E> for AsyncListener<E> {
+    type Output = BoxFuture<bool>;
+
+    fn call(&self, input: E) -> Self::Output {
+        self.func.call(input)
+    }
+}
+
+type SharedListener<E> = Arc<AsyncListener<E>>;
+
+/// A transport which hands every event to its listeners as futures
+/// spawned on an [`Executor`].
+pub struct Async<E: 'static, X> {
+    listener: sync::MutexState<Arena<SharedListener<E>>>,
+    executor: X,
+}
+
+impl<E, X: Clone> Clone for Async<E, X> {
+    fn clone(&self) -> Self {
+        Async {
+            listener: self.listener.clone(),
+            executor: self.executor.clone(),
+        }
+    }
+}
+
+impl<E, X> Async<E, X> {
+    pub fn new(executor: X) -> Async<E, X> {
+        Async {
+            listener: sync::MutexState::new(Arena::default()),
+            executor,
+        }
+    }
+
+    pub fn executor(&self) -> &X {
+        &self.executor
+    }
+}
+
+impl<E, X> Default for Async<E, X>
+where
+    X: Default,
+{
+    fn default() -> Self {
+        Async::new(X::default())
+    }
+}
+
+impl<E, X> Transport<'static, E> for Async<E, X>
+where
+    E: Clone,
+    X: Executor,
+{
+    type Listener = AsyncListener<E>;
+
+    type Subscription = AsyncSubscription<E>;
+
+    fn create_listener<L>(&self, listener: L) -> Self::Subscription
+    where
+        L: IntoListener<'static, Self::Listener, E>,
+    {
+        let listener = Arc::new(listener.into_listener());
+        let idx = self
+            .listener
+            .write(move |listeners| listeners.insert(listener))
+            .unwrap();
+
+        AsyncSubscription {
+            idx: Some(idx),
+            arena: self.listener.downgrade(),
+        }
+    }
+
+    fn listener_count(&self) -> usize {
+        self.listener.read(|arena| arena.len()).unwrap_or(0)
+    }
+
+    fn trigger(&self, event: E) {
+        // Listeners are called unlocked, so they can emit and listen themselves
+        let snapshot = match self.listener.read(|arena| {
+            arena
+                .iter()
+                .map(|(idx, next)| (idx, next.clone()))
+                .collect::<Vec<_>>()
+        }) {
+            Ok(snapshot) => snapshot,
+            Err(_) => return,
+        };
+
+        for (idx, next) in snapshot {
+            let future = next.func.call(event.clone());
+            let arena = self.listener.downgrade();
+            self.executor.spawn(async move {
+                if !future.await {
+                    arena.write(|arena| arena.remove(idx)).ok();
+                }
+            });
+        }
+    }
+}
+
+pub struct AsyncSubscription<E>
+where
+    E: 'static,
+{
+    arena: sync::WeakMutexState<Arena<SharedListener<E>>>,
+    idx: Option<Index>,
+}
+
+impl<E> Subscription for AsyncSubscription<E> {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn detach(mut self) {
+        self.idx = None;
+    }
+
+    fn is_closed(&self) -> bool {
+        match self.idx {
+            Some(idx) => !self
+                .arena
+                .read(|arena| arena.contains(idx))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+impl<E> Drop for AsyncSubscription<E> {
+    fn drop(&mut self) {
+        let idx = match self.idx.take() {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        self.arena.write(|arena| arena.remove(idx)).ok();
+    }
+}
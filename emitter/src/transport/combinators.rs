@@ -0,0 +1,473 @@
+use core::marker::PhantomData;
+use core::time::Duration;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex, MutexGuard, PoisonError, Weak,
+};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+
+use state::{StateError, StateTrait};
+
+use super::{
+    func::{Callback, CallbackMut},
+    reply::{Flow, Reply},
+    timer::{Job, Timer},
+};
+
+/// Only passes events matching the predicate on to the callback.
+pub struct Filter<P, C> {
+    pub(crate) callback: C,
+    pub(crate) predicate: P,
+}
+
+impl<T, P, C> Callback<T> for Filter<P, C>
+where
+    P: Fn(&T) -> bool,
+    C: Callback<T>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &T) -> Self::Output {
+        if (self.predicate)(input) {
+            self.callback.call(input).into_flow()
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+impl<T, P, C> CallbackMut<T> for Filter<P, C>
+where
+    P: FnMut(&T) -> bool,
+    C: CallbackMut<T>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &T) -> Self::Output {
+        if (self.predicate)(input) {
+            self.callback.call_mut(input).into_flow()
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+/// Maps events with the function and passes the `Some` values on to the callback.
+pub struct FilterMap<F, C> {
+    pub(crate) callback: C,
+    pub(crate) func: F,
+}
+
+impl<T, U, F, C> Callback<T> for FilterMap<F, C>
+where
+    F: Fn(&T) -> Option<U>,
+    C: Callback<U>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &T) -> Self::Output {
+        match (self.func)(input) {
+            Some(next) => self.callback.call(&next).into_flow(),
+            None => Flow::Continue,
+        }
+    }
+}
+
+impl<T, U, F, C> CallbackMut<T> for FilterMap<F, C>
+where
+    F: FnMut(&T) -> Option<U>,
+    C: CallbackMut<U>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &T) -> Self::Output {
+        match (self.func)(input) {
+            Some(next) => self.callback.call_mut(&next).into_flow(),
+            None => Flow::Continue,
+        }
+    }
+}
+
+/// Calls the callback at most once per interval and drops the events in between.
+///
+/// Not available on `wasm32-unknown-unknown`, which has no clock.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub struct Throttle<C> {
+    pub(crate) callback: C,
+    pub(crate) interval: Duration,
+    pub(crate) last: Mutex<Option<Instant>>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<C> Throttle<C> {
+    fn ready(last: &mut Option<Instant>, interval: Duration) -> bool {
+        let now = Instant::now();
+        match last {
+            Some(last) if now.duration_since(*last) < interval => false,
+            _ => {
+                *last = Some(now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<T, C> Callback<T> for Throttle<C>
+where
+    C: Callback<T>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &T) -> Self::Output {
+        let ready = Throttle::<C>::ready(&mut self.last.lock().expect("lock"), self.interval);
+        if ready {
+            self.callback.call(input).into_flow()
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<T, C> CallbackMut<T> for Throttle<C>
+where
+    C: CallbackMut<T>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &T) -> Self::Output {
+        let ready = Throttle::<C>::ready(self.last.get_mut().expect("lock"), self.interval);
+        if ready {
+            self.callback.call_mut(input).into_flow()
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+/// Calls the callback for the first `n` events and unsubscribes afterwards.
+pub struct Take<C> {
+    pub(crate) callback: C,
+    pub(crate) remaining: AtomicUsize,
+}
+
+impl<C> Take<C> {
+    // Returns `None` when there are no calls left, otherwise whether this was the last one
+    fn take(remaining: &AtomicUsize) -> Option<bool> {
+        remaining
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+            .ok()
+            .map(|n| n == 1)
+    }
+
+    fn reply(last: Option<bool>, reply: impl FnOnce() -> Flow) -> Flow {
+        match last {
+            None => Flow::Unsubscribe,
+            Some(true) => {
+                reply();
+                Flow::Unsubscribe
+            }
+            Some(false) => reply(),
+        }
+    }
+}
+
+impl<T, C> Callback<T> for Take<C>
+where
+    C: Callback<T>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &T) -> Self::Output {
+        let last = Take::<C>::take(&self.remaining);
+        Take::<C>::reply(last, || self.callback.call(input).into_flow())
+    }
+}
+
+impl<T, C> CallbackMut<T> for Take<C>
+where
+    C: CallbackMut<T>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &T) -> Self::Output {
+        let last = Take::<C>::take(&self.remaining);
+        let callback = &mut self.callback;
+        Take::<C>::reply(last, || callback.call_mut(input).into_flow())
+    }
+}
+
+/// Unsubscribes once the owning state has been dropped or emptied.
+pub struct OwnedBy<W, T, C> {
+    pub(crate) callback: C,
+    pub(crate) owner: W,
+    pub(crate) _t: PhantomData<fn() -> T>,
+}
+
+impl<E, W, T, C> Callback<E> for OwnedBy<W, T, C>
+where
+    W: StateTrait<T>,
+    C: Callback<E>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &E) -> Self::Output {
+        if self.owner.is_valid() {
+            self.callback.call(input).into_flow()
+        } else {
+            Flow::Unsubscribe
+        }
+    }
+}
+
+impl<E, W, T, C> CallbackMut<E> for OwnedBy<W, T, C>
+where
+    W: StateTrait<T>,
+    C: CallbackMut<E>,
+    C::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &E) -> Self::Output {
+        if self.owner.is_valid() {
+            self.callback.call_mut(input).into_flow()
+        } else {
+            Flow::Unsubscribe
+        }
+    }
+}
+
+/// Calls the function with the upgraded state for each event.
+/// Unsubscribes once the state can no longer be upgraded.
+pub struct WeakListener<W, T, F> {
+    pub(crate) state: W,
+    pub(crate) func: F,
+    pub(crate) _t: PhantomData<fn() -> T>,
+}
+
+impl<W, T, F> WeakListener<W, T, F> {
+    fn reply<R: Reply>(ret: Result<R, StateError>) -> Flow {
+        match ret {
+            Ok(ret) => ret.into_flow(),
+            Err(StateError::Upgrade | StateError::ReadOnly | StateError::Duplicate) => {
+                Flow::Unsubscribe
+            }
+            // The owner is alive, but has nothing to hand out right now
+            Err(StateError::Empty | StateError::Timeout) => Flow::Continue,
+        }
+    }
+}
+
+impl<E, W, T, F, R> Callback<E> for WeakListener<W, T, F>
+where
+    W: StateTrait<T>,
+    F: Fn(&mut T, &E) -> R,
+    R: Reply,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &E) -> Self::Output {
+        Self::reply(self.state.write(|state| (self.func)(state, input)))
+    }
+}
+
+impl<E, W, T, F, R> CallbackMut<E> for WeakListener<W, T, F>
+where
+    W: StateTrait<T>,
+    F: FnMut(&mut T, &E) -> R,
+    R: Reply,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &E) -> Self::Output {
+        let func = &mut self.func;
+        Self::reply(self.state.write(|state| func(state, input)))
+    }
+}
+
+struct Pending<T> {
+    generation: u64,
+    event: Option<T>,
+    unsubscribed: bool,
+}
+
+struct DebounceShared<T, C> {
+    pending: Mutex<Pending<T>>,
+    callback: Mutex<C>,
+    // Kept so jobs and drop can call back without the callback bounds
+    deliver: fn(&DebounceShared<T, C>, Option<u64>),
+}
+
+impl<T, C> DebounceShared<T, C> {
+    fn pending(&self) -> MutexGuard<'_, Pending<T>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Calls back with the pending event, unless a newer one arrived since `generation`
+    fn deliver(&self, generation: Option<u64>, call: impl FnOnce(&mut C, &T) -> Flow) {
+        let event = {
+            let mut pending = self.pending();
+            if pending.unsubscribed || generation.is_some_and(|g| g != pending.generation) {
+                return;
+            }
+            pending.event.take()
+        };
+
+        if let Some(event) = event {
+            let mut callback = self.callback.lock().unwrap_or_else(PoisonError::into_inner);
+            if call(&mut callback, &event) == Flow::Unsubscribe {
+                self.pending().unsubscribed = true;
+            }
+        }
+    }
+
+    fn deliver_ref(&self, generation: Option<u64>)
+    where
+        C: Callback<T>,
+        C::Output: Reply,
+    {
+        self.deliver(generation, |callback, event| {
+            callback.call(event).into_flow()
+        })
+    }
+
+    fn deliver_mut(&self, generation: Option<u64>)
+    where
+        C: CallbackMut<T>,
+        C::Output: Reply,
+    {
+        self.deliver(generation, |callback, event| {
+            callback.call_mut(event).into_flow()
+        })
+    }
+}
+
+/// The trailing call of a [`Debounce`], skipped if another event arrived meanwhile.
+pub struct DebounceJob<T, C> {
+    shared: Weak<DebounceShared<T, C>>,
+    generation: u64,
+}
+
+impl<T, C> Job for DebounceJob<T, C> {
+    fn run(self) {
+        if let Some(shared) = self.shared.upgrade() {
+            (shared.deliver)(&shared, Some(self.generation));
+        }
+    }
+}
+
+/// Calls the callback with the latest event once no events have
+/// arrived for the given interval.
+///
+/// The call is scheduled on the [`Timer`], which decides where it runs.
+/// An event still pending when the listener is dropped is passed on right away.
+pub struct Debounce<T, C, X> {
+    shared: Arc<DebounceShared<T, C>>,
+    interval: Duration,
+    timer: X,
+}
+
+impl<T, C, X> Debounce<T, C, X> {
+    fn new(
+        callback: C,
+        interval: Duration,
+        timer: X,
+        deliver: fn(&DebounceShared<T, C>, Option<u64>),
+    ) -> Debounce<T, C, X> {
+        Debounce {
+            shared: Arc::new(DebounceShared {
+                pending: Mutex::new(Pending {
+                    generation: 0,
+                    event: None,
+                    unsubscribed: false,
+                }),
+                callback: Mutex::new(callback),
+                deliver,
+            }),
+            interval,
+            timer,
+        }
+    }
+
+    pub(crate) fn with_callback(callback: C, interval: Duration, timer: X) -> Debounce<T, C, X>
+    where
+        C: Callback<T>,
+        C::Output: Reply,
+    {
+        Debounce::new(callback, interval, timer, DebounceShared::deliver_ref)
+    }
+
+    pub(crate) fn with_callback_mut(callback: C, interval: Duration, timer: X) -> Debounce<T, C, X>
+    where
+        C: CallbackMut<T>,
+        C::Output: Reply,
+    {
+        Debounce::new(callback, interval, timer, DebounceShared::deliver_mut)
+    }
+}
+
+impl<T, C, X> Debounce<T, C, X>
+where
+    T: Clone,
+    X: Timer<DebounceJob<T, C>>,
+{
+    fn push(&self, input: &T) -> Flow {
+        let generation = {
+            let mut pending = self.shared.pending();
+            if pending.unsubscribed {
+                return Flow::Unsubscribe;
+            }
+            pending.event = Some(input.clone());
+            pending.generation += 1;
+            pending.generation
+        };
+
+        let job = DebounceJob {
+            shared: Arc::downgrade(&self.shared),
+            generation,
+        };
+        self.timer.schedule(self.interval, job);
+        Flow::Continue
+    }
+}
+
+impl<T, C, X> Callback<T> for Debounce<T, C, X>
+where
+    T: Clone,
+    X: Timer<DebounceJob<T, C>>,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &T) -> Self::Output {
+        self.push(input)
+    }
+}
+
+impl<T, C, X> CallbackMut<T> for Debounce<T, C, X>
+where
+    T: Clone,
+    X: Timer<DebounceJob<T, C>>,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &T) -> Self::Output {
+        self.push(input)
+    }
+}
+
+impl<T, C, X> Drop for Debounce<T, C, X> {
+    fn drop(&mut self) {
+        (self.shared.deliver)(&self.shared, None);
+    }
+}
@@ -0,0 +1,320 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::rc::Rc;
+
+use generational_arena::Index;
+use state::{Downgrade, State, StateTrait, WeakState};
+
+use crate::{
+    transport::{
+        func::Func,
+        listeners::Listeners,
+        reply::{Flow, Reply},
+    },
+    Emitter, IntoListener, Listener, Subscription, Transport,
+};
+
+pub type LocalBoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Spawns futures which don't need to be `Send` on the current thread,
+/// eg. on a tokio `LocalSet` or with `wasm_bindgen_futures::spawn_local`.
+pub trait LocalExecutor {
+    fn spawn_local<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static;
+}
+
+impl<X> LocalExecutor for &X
+where
+    X: LocalExecutor,
+{
+    fn spawn_local<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        (**self).spawn_local(future)
+    }
+}
+
+impl<X> LocalExecutor for Rc<X>
+where
+    X: LocalExecutor,
+{
+    fn spawn_local<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        (**self).spawn_local(future)
+    }
+}
+
+/// Spawns listener futures on the current tokio `LocalSet`.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokioLocal;
+
+#[cfg(feature = "tokio")]
+impl LocalExecutor for TokioLocal {
+    fn spawn_local<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        tokio::task::spawn_local(future);
+    }
+}
+
+#[cfg(feature = "futures")]
+impl LocalExecutor for futures::executor::LocalSpawner {
+    fn spawn_local<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        futures::task::LocalSpawnExt::spawn_local(self, future).ok();
+    }
+}
+
+pub struct LocalAsyncListener<E> {
+    func: Box<dyn Func<E, Output = LocalBoxFuture<Flow>>>,
+}
+
+impl<E> LocalAsyncListener<E> {
+    pub fn new<F>(func: F) -> LocalAsyncListener<E>
+    where
+        E: 'static,
+        F: Func<E> + 'static,
+        F::Output: Future + 'static,
+        <F::Output as Future>::Output: Reply,
+    {
+        LocalAsyncListener {
+            func: Box::new(move |event: E| {
+                let future = func.call(event);
+                Box::pin(async move { future.await.into_flow() }) as LocalBoxFuture<Flow>
+            }),
+        }
+    }
+}
+
+impl<E> Listener<'static, E> for LocalAsyncListener<E> {}
+
+impl<E> Func<E> for LocalAsyncListener<E> {
+    type Output = LocalBoxFuture<Flow>;
+
+    fn call(&self, input: E) -> Self::Output {
+        self.func.call(input)
+    }
+}
+
+type SharedListener<E> = Rc<LocalAsyncListener<E>>;
+
+/// A single threaded transport for async listeners whose futures aren't `Send`.
+///
+/// [`emit`](Emitter::emit) spawns the listener futures on a [`LocalExecutor`],
+/// while [`emit_async`](Emitter::emit_async) runs them concurrently and
+/// returns once all of them are done. As the listeners run concurrently,
+/// [`Flow::Stop`] has no effect.
+pub struct LocalAsync<E: 'static, X> {
+    listener: State<Listeners<SharedListener<E>>>,
+    executor: X,
+}
+
+impl<E, X: Clone> Clone for LocalAsync<E, X> {
+    fn clone(&self) -> Self {
+        LocalAsync {
+            listener: self.listener.clone(),
+            executor: self.executor.clone(),
+        }
+    }
+}
+
+impl<E, X> LocalAsync<E, X> {
+    pub fn new(executor: X) -> LocalAsync<E, X> {
+        LocalAsync {
+            listener: State::new(Listeners::default()),
+            executor,
+        }
+    }
+
+    pub fn executor(&self) -> &X {
+        &self.executor
+    }
+
+    fn futures(&self, event: E) -> Vec<(Index, LocalBoxFuture<Flow>)>
+    where
+        E: Clone,
+    {
+        let snapshot = match self.listener.read(|arena| arena.snapshot()) {
+            Ok(snapshot) => snapshot,
+            Err(_) => return Vec::new(),
+        };
+
+        snapshot
+            .iter()
+            .map(|(idx, next)| (idx, next.call(event.clone())))
+            .collect()
+    }
+
+    /// Call every listener and wait for all of their futures to finish.
+    pub async fn trigger_async(&self, event: E)
+    where
+        E: Clone,
+    {
+        let futures = self
+            .futures(event)
+            .into_iter()
+            .map(|(idx, future)| (idx, Some(future)))
+            .collect();
+
+        let unsubscribed = JoinAll {
+            futures,
+            unsubscribed: Vec::new(),
+        }
+        .await;
+
+        self.listener
+            .write(|arena| arena.remove_all(unsubscribed))
+            .ok();
+    }
+}
+
+impl<E, X> Default for LocalAsync<E, X>
+where
+    X: Default,
+{
+    fn default() -> Self {
+        LocalAsync::new(X::default())
+    }
+}
+
+impl<E, X> Transport<'static, E> for LocalAsync<E, X>
+where
+    E: Clone,
+    X: LocalExecutor,
+{
+    type Listener = LocalAsyncListener<E>;
+
+    type Subscription = LocalAsyncSubscription<E>;
+
+    fn create_listener<L>(&self, listener: L) -> Self::Subscription
+    where
+        L: IntoListener<'static, Self::Listener, E>,
+    {
+        self.create_listener_with_priority(listener, 0)
+    }
+
+    /// Listeners with a higher priority are called, and their futures spawned, first.
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
+    where
+        L: IntoListener<'static, Self::Listener, E>,
+    {
+        let listener = Rc::new(listener.into_listener());
+        let idx = self
+            .listener
+            .write(|arena| arena.insert(listener, priority))
+            .unwrap();
+
+        LocalAsyncSubscription {
+            idx: Some(idx),
+            arena: self.listener.downgrade(),
+        }
+    }
+
+    fn listener_count(&self) -> usize {
+        self.listener.read(|arena| arena.len()).unwrap_or(0)
+    }
+
+    fn trigger(&self, event: E) {
+        for (idx, future) in self.futures(event) {
+            let arena = self.listener.downgrade();
+            self.executor.spawn_local(async move {
+                if future.await == Flow::Unsubscribe {
+                    arena.write(|arena| arena.remove(idx)).ok();
+                }
+            });
+        }
+    }
+}
+
+struct JoinAll {
+    futures: Vec<(Index, Option<LocalBoxFuture<Flow>>)>,
+    unsubscribed: Vec<Index>,
+}
+
+impl Future for JoinAll {
+    type Output = Vec<Index>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut done = true;
+
+        for (idx, slot) in &mut this.futures {
+            let future = match slot {
+                Some(future) => future,
+                None => continue,
+            };
+            match future.as_mut().poll(cx) {
+                Poll::Ready(flow) => {
+                    if flow == Flow::Unsubscribe {
+                        this.unsubscribed.push(*idx);
+                    }
+                    *slot = None;
+                }
+                Poll::Pending => done = false,
+            }
+        }
+
+        match done {
+            true => Poll::Ready(core::mem::take(&mut this.unsubscribed)),
+            false => Poll::Pending,
+        }
+    }
+}
+
+pub struct LocalAsyncSubscription<E: 'static> {
+    arena: WeakState<Listeners<SharedListener<E>>>,
+    idx: Option<Index>,
+}
+
+impl<E> Subscription for LocalAsyncSubscription<E> {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn detach(mut self) {
+        self.idx = None;
+    }
+
+    fn is_closed(&self) -> bool {
+        match self.idx {
+            Some(idx) => !self
+                .arena
+                .read(|arena| arena.contains(idx))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+impl<E> Drop for LocalAsyncSubscription<E> {
+    fn drop(&mut self) {
+        let idx = match self.idx.take() {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        self.arena.write(|arena| arena.remove(idx)).ok();
+    }
+}
+
+impl<E, X> Emitter<E, LocalAsync<E, X>>
+where
+    E: Clone,
+    X: LocalExecutor,
+{
+    /// Emit an event and wait for every listener future to finish.
+    pub async fn emit_async(&self, event: E) {
+        self.transport().trigger_async(event).await
+    }
+}
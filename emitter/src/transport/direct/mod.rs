@@ -1,6 +1,7 @@
+mod local;
 mod non_send;
 
-pub use self::non_send::*;
+pub use self::{local::*, non_send::*};
 
 #[cfg(feature = "sync")]
 pub mod sync;
@@ -1,13 +1,16 @@
+use std::{cell::RefCell, rc::Rc};
+
 use crate::transport::{
     func::{CallbackMut, CallbackMutExt},
-    reply::Reply,
-    IntoListener, Listener, Subscription, Transport,
+    listeners::{Dispatch, Listeners, Unhandled},
+    reply::{Flow, Reply},
+    IntoListener, Listener, ListenerStats, PanicHandler, Subscription, Transport,
 };
-use generational_arena::{Arena, Index};
+use generational_arena::Index;
 use state::{Downgrade, State, StateTrait, WeakState};
 
 pub struct DirectListener<'a, E> {
-    func: Box<dyn CallbackMut<E, Output = bool> + 'a>,
+    func: Box<dyn CallbackMut<E, Output = Flow> + 'a>,
 }
 
 impl<'a, E> DirectListener<'a, E> {
@@ -18,13 +21,13 @@ impl<'a, E> DirectListener<'a, E> {
         F::Output: Reply,
     {
         DirectListener {
-            func: Box::new(func.map(|ret: F::Output| ret.carry_on())),
+            func: Box::new(func.map(|ret: F::Output| ret.into_flow())),
         }
     }
 }
 
 impl<'a, E> CallbackMut<E> for DirectListener<'a, E> {
-    type Output = bool;
+    type Output = Flow;
 
     fn call_mut(&mut self, input: &E) -> Self::Output {
         self.func.call_mut(input)
@@ -33,8 +36,10 @@ impl<'a, E> CallbackMut<E> for DirectListener<'a, E> {
 
 impl<'a, E> Listener<'a, E> for DirectListener<'a, E> {}
 
+type SharedListener<'a, E> = Rc<RefCell<DirectListener<'a, E>>>;
+
 pub struct DirectSubscription<'a, E> {
-    arena: WeakState<Arena<DirectListener<'a, E>>>,
+    arena: WeakState<Listeners<SharedListener<'a, E>>>,
     idx: Option<Index>,
 }
 
@@ -46,6 +51,16 @@ impl<'a, E> Subscription for DirectSubscription<'a, E> {
     fn detach(mut self) {
         self.idx = None;
     }
+
+    fn is_closed(&self) -> bool {
+        match self.idx {
+            Some(idx) => !self
+                .arena
+                .read(|arena| arena.contains(idx))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
 }
 
 impl<'a, E> Drop for DirectSubscription<'a, E> {
@@ -60,13 +75,15 @@ impl<'a, E> Drop for DirectSubscription<'a, E> {
 }
 
 pub struct Direct<'a, E> {
-    listener: State<Arena<DirectListener<'a, E>>>,
+    listener: State<Listeners<SharedListener<'a, E>>>,
+    dispatch: State<Dispatch<E>>,
 }
 
 impl<'a, E> Clone for Direct<'a, E> {
     fn clone(&self) -> Self {
         Direct {
             listener: self.listener.clone(),
+            dispatch: self.dispatch.clone(),
         }
     }
 }
@@ -80,8 +97,18 @@ impl<'a, E> Transport<'a, E> for Direct<'a, E> {
     where
         L: IntoListener<'a, Self::Listener, E>,
     {
-        let listener = listener.into_listener();
-        let idx = self.listener.write(|arena| arena.insert(listener)).unwrap();
+        self.create_listener_with_priority(listener, 0)
+    }
+
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
+    where
+        L: IntoListener<'a, Self::Listener, E>,
+    {
+        let listener = Rc::new(RefCell::new(listener.into_listener()));
+        let idx = self
+            .listener
+            .write(|arena| arena.insert(listener, priority))
+            .unwrap();
 
         DirectSubscription {
             idx: Some(idx),
@@ -89,21 +116,63 @@ impl<'a, E> Transport<'a, E> for Direct<'a, E> {
         }
     }
 
-    fn trigger(&self, event: E) {
+    fn set_panic_handler(&self, handler: PanicHandler) {
         self.listener
-            .write(|arena| {
-                for (_idx, next) in arena.iter_mut() {
-                    next.func.call_mut(&event);
-                }
-            })
+            .write(|arena| arena.set_panic_handler(handler))
             .ok();
     }
+
+    fn listener_count(&self) -> usize {
+        self.listener.read(|arena| arena.len()).unwrap_or(0)
+    }
+
+    fn pending(&self) -> usize {
+        self.dispatch.read(|dispatch| dispatch.len()).unwrap_or(0)
+    }
+
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.listener
+            .read(|arena| arena.stats())
+            .unwrap_or_default()
+    }
+
+    fn trigger(&self, event: E) {
+        // Events triggered from within a listener are delivered after the current one
+        if !self
+            .dispatch
+            .write(|dispatch| dispatch.enqueue(event))
+            .unwrap()
+        {
+            return;
+        }
+
+        let mut unhandled = Unhandled::default();
+        while let Some(event) = self.dispatch.write(|dispatch| dispatch.next()).unwrap() {
+            let snapshot = match self.listener.read(|arena| arena.snapshot()) {
+                Ok(snapshot) => snapshot,
+                Err(_) => continue,
+            };
+
+            let unsubscribed = snapshot.dispatch(
+                |next| next.borrow_mut().func.call_mut(&event),
+                &mut unhandled,
+            );
+
+            self.listener
+                .write(|arena| arena.remove_all(unsubscribed))
+                .ok();
+        }
+
+        // Only once the queue is done, so the next trigger starts dispatching again
+        unhandled.resume();
+    }
 }
 
 impl<'a, E> Default for Direct<'a, E> {
     fn default() -> Self {
         Direct {
-            listener: State::new(Arena::default()),
+            listener: State::new(Listeners::default()),
+            dispatch: State::new(Dispatch::default()),
         }
     }
 }
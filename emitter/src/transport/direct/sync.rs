@@ -1,15 +1,25 @@
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
 use crate::{
     transport::{
         func::{Callback, CallbackExt},
-        reply::Reply,
+        listeners::{Dispatch, Listeners, Unhandled},
+        reply::{Flow, Reply},
     },
-    IntoListener, Listener, Subscription, Transport,
+    IntoListener, Listener, ListenerStats, PanicHandler, Subscription, Transport,
 };
-use generational_arena::{Arena, Index};
+use generational_arena::Index;
+use parking_lot::ReentrantMutex;
 use state::{sync, Downgrade, StateTrait};
 
 pub struct SyncListener<E> {
-    func: Box<dyn Callback<E, Output = bool> + Send + Sync>,
+    func: Box<dyn Callback<E, Output = Flow> + Send + Sync>,
 }
 
 impl<E> SyncListener<E> {
@@ -20,7 +30,7 @@ impl<E> SyncListener<E> {
         F::Output: Reply,
     {
         SyncListener {
-            func: Box::new(func.map(|ret: F::Output| ret.carry_on())),
+            func: Box::new(func.map(|ret: F::Output| ret.into_flow())),
         }
     }
 }
@@ -28,7 +38,7 @@ impl<E> SyncListener<E> {
 impl<E> Listener<'static, E> for SyncListener<E> {}
 
 impl<E> Callback<E> for SyncListener<E> {
-    type Output = bool;
+    type Output = Flow;
 
     fn call(&self, input: &E) -> Self::Output {
         self.func.call(input)
@@ -36,13 +46,20 @@ impl<E> Callback<E> for SyncListener<E> {
 }
 
 pub struct SyncDirect<E: 'static> {
-    listener: sync::MutexState<Arena<SyncListener<E>>>,
+    listener: sync::MutexState<Listeners<Arc<SyncListener<E>>>>,
+    // Serializes dispatching between threads, while letting a listener
+    // re-enter the transport on its own thread.
+    dispatch: Arc<ReentrantMutex<RefCell<Dispatch<E>>>>,
+    // Counted outside of the dispatch lock, so it includes threads waiting on it
+    pending: Arc<AtomicUsize>,
 }
 
 impl<E> Clone for SyncDirect<E> {
     fn clone(&self) -> Self {
         SyncDirect {
             listener: self.listener.clone(),
+            dispatch: self.dispatch.clone(),
+            pending: self.pending.clone(),
         }
     }
 }
@@ -56,10 +73,17 @@ impl<E> Transport<'static, E> for SyncDirect<E> {
     where
         L: IntoListener<'static, Self::Listener, E>,
     {
-        let listener = listener.into_listener();
+        self.create_listener_with_priority(listener, 0)
+    }
+
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
+    where
+        L: IntoListener<'static, Self::Listener, E>,
+    {
+        let listener = Arc::new(listener.into_listener());
         let idx = self
             .listener
-            .write(move |listeners| listeners.insert(listener))
+            .write(move |listeners| listeners.insert(listener, priority))
             .unwrap();
 
         SyncDirectSubscription {
@@ -68,22 +92,64 @@ impl<E> Transport<'static, E> for SyncDirect<E> {
         }
     }
 
-    fn trigger(&self, event: E) {
+    fn set_panic_handler(&self, handler: PanicHandler) {
         self.listener
-            .read(|arena| {
-                for (_idx, next) in arena.iter() {
-                    next.func.call(&event);
-                }
-            })
+            .write(|arena| arena.set_panic_handler(handler))
             .ok();
     }
+
+    fn listener_count(&self) -> usize {
+        self.listener.read(|arena| arena.len()).unwrap_or(0)
+    }
+
+    fn pending(&self) -> usize {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.listener
+            .read(|arena| arena.stats())
+            .unwrap_or_default()
+    }
+
+    fn trigger(&self, event: E) {
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        let dispatch = self.dispatch.lock();
+
+        // Events triggered from within a listener are delivered after the current one
+        if !dispatch.borrow_mut().enqueue(event) {
+            return;
+        }
+
+        let mut unhandled = Unhandled::default();
+        loop {
+            let event = match dispatch.borrow_mut().next() {
+                Some(event) => event,
+                None => break,
+            };
+
+            if let Ok(snapshot) = self.listener.read(|arena| arena.snapshot()) {
+                let unsubscribed = snapshot.dispatch(|next| next.func.call(&event), &mut unhandled);
+
+                self.listener
+                    .write(|arena| arena.remove_all(unsubscribed))
+                    .ok();
+            }
+
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+        }
+
+        // Only once the queue is done, so the next trigger starts dispatching again
+        drop(dispatch);
+        unhandled.resume();
+    }
 }
 
 pub struct SyncDirectSubscription<E>
 where
     E: 'static,
 {
-    arena: sync::WeakMutexState<Arena<SyncListener<E>>>,
+    arena: sync::WeakMutexState<Listeners<Arc<SyncListener<E>>>>,
     idx: Option<Index>,
 }
 
@@ -95,6 +161,16 @@ impl<E> Subscription for SyncDirectSubscription<E> {
     fn detach(mut self) {
         self.idx = None;
     }
+
+    fn is_closed(&self) -> bool {
+        match self.idx {
+            Some(idx) => !self
+                .arena
+                .read(|arena| arena.contains(idx))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
 }
 
 impl<E> Drop for SyncDirectSubscription<E> {
@@ -111,7 +187,9 @@ impl<E> Drop for SyncDirectSubscription<E> {
 impl<E> Default for SyncDirect<E> {
     fn default() -> Self {
         SyncDirect {
-            listener: sync::MutexState::new(Arena::default()),
+            listener: sync::MutexState::new(Listeners::default()),
+            dispatch: Arc::default(),
+            pending: Arc::default(),
         }
     }
 }
@@ -1,4 +1,17 @@
-use core::marker::PhantomData;
+use core::{marker::PhantomData, time::Duration};
+use std::sync::atomic::AtomicUsize;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::sync::Mutex;
+
+use state::{Downgrade, StateTrait};
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use super::combinators::Throttle;
+use super::{
+    combinators::{Debounce, DebounceJob, Filter, FilterMap, OwnedBy, Take},
+    reply::Reply,
+    timer::Timer,
+};
 
 pub trait Func<T> {
     type Output;
@@ -44,6 +57,78 @@ pub trait CallbackExt<T>: Callback<T> {
             _t: PhantomData,
         }
     }
+
+    fn filter<P>(self, predicate: P) -> Filter<P, Self>
+    where
+        Self: Sized,
+        P: Fn(&T) -> bool,
+    {
+        Filter {
+            callback: self,
+            predicate,
+        }
+    }
+
+    /// Map events of another type to `T`, skipping the ones mapped to `None`.
+    fn filter_map<F, I>(self, func: F) -> FilterMap<F, Self>
+    where
+        Self: Sized,
+        F: Fn(&I) -> Option<T>,
+    {
+        FilterMap {
+            callback: self,
+            func,
+        }
+    }
+
+    /// Drop events arriving less than `interval` after the last one passed on.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn throttle(self, interval: Duration) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        Throttle {
+            callback: self,
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Only pass on the latest event of a burst, once no events arrived for `interval`.
+    /// The trailing calls are scheduled on `timer`, eg. a [`ThreadTimer`](super::ThreadTimer).
+    fn debounce<X>(self, interval: Duration, timer: X) -> Debounce<T, Self, X>
+    where
+        Self: Sized,
+        Self::Output: Reply,
+        X: Timer<DebounceJob<T, Self>>,
+    {
+        Debounce::with_callback(self, interval, timer)
+    }
+
+    /// Unsubscribe after the first `n` events.
+    fn take(self, n: usize) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take {
+            callback: self,
+            remaining: AtomicUsize::new(n),
+        }
+    }
+
+    /// Unsubscribe once `owner` has been dropped, without keeping it alive.
+    fn owned_by<S, O>(self, owner: &S) -> OwnedBy<S::Output, O, Self>
+    where
+        Self: Sized,
+        S: Downgrade,
+        S::Output: StateTrait<O>,
+    {
+        OwnedBy {
+            callback: self,
+            owner: owner.downgrade(),
+            _t: PhantomData,
+        }
+    }
 }
 
 impl<T, C> CallbackExt<T> for C where C: Callback<T> {}
@@ -98,6 +183,78 @@ pub trait CallbackMutExt<T>: CallbackMut<T> {
             _t: PhantomData,
         }
     }
+
+    fn filter<P>(self, predicate: P) -> Filter<P, Self>
+    where
+        Self: Sized,
+        P: FnMut(&T) -> bool,
+    {
+        Filter {
+            callback: self,
+            predicate,
+        }
+    }
+
+    /// Map events of another type to `T`, skipping the ones mapped to `None`.
+    fn filter_map<F, I>(self, func: F) -> FilterMap<F, Self>
+    where
+        Self: Sized,
+        F: FnMut(&I) -> Option<T>,
+    {
+        FilterMap {
+            callback: self,
+            func,
+        }
+    }
+
+    /// Drop events arriving less than `interval` after the last one passed on.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn throttle(self, interval: Duration) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        Throttle {
+            callback: self,
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Only pass on the latest event of a burst, once no events arrived for `interval`.
+    /// The trailing calls are scheduled on `timer`, eg. a [`ThreadTimer`](super::ThreadTimer).
+    fn debounce<X>(self, interval: Duration, timer: X) -> Debounce<T, Self, X>
+    where
+        Self: Sized,
+        Self::Output: Reply,
+        X: Timer<DebounceJob<T, Self>>,
+    {
+        Debounce::with_callback_mut(self, interval, timer)
+    }
+
+    /// Unsubscribe after the first `n` events.
+    fn take(self, n: usize) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take {
+            callback: self,
+            remaining: AtomicUsize::new(n),
+        }
+    }
+
+    /// Unsubscribe once `owner` has been dropped, without keeping it alive.
+    fn owned_by<S, O>(self, owner: &S) -> OwnedBy<S::Output, O, Self>
+    where
+        Self: Sized,
+        S: Downgrade,
+        S::Output: StateTrait<O>,
+    {
+        OwnedBy {
+            callback: self,
+            owner: owner.downgrade(),
+            _t: PhantomData,
+        }
+    }
 }
 
 impl<T, C> CallbackMutExt<T> for C where C: CallbackMut<T> {}
@@ -136,7 +293,7 @@ where
     }
 }
 
-impl<'a, E: Clone + 'static> CallbackMut<E> for std::sync::mpsc::Sender<E> {
+impl<E: Clone + 'static> CallbackMut<E> for std::sync::mpsc::Sender<E> {
     type Output = Result<(), std::sync::mpsc::SendError<E>>;
     fn call_mut(&mut self, event: &E) -> Self::Output {
         self.send(event.clone())
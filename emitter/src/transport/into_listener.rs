@@ -1,13 +1,16 @@
-use crate::DirectListener;
+use crate::{DirectListener, LocalAsyncListener};
 #[allow(unused_imports)]
 use crate::{IntoListener, Listener};
 
 #[cfg(feature = "sync")]
 use crate::direct::sync::SyncListener;
 
+#[cfg(feature = "async")]
+use crate::r#async::AsyncListener;
+
 #[allow(unused_imports)]
 use super::{
-    func::{Callback, CallbackExt, CallbackMut},
+    func::{Callback, CallbackMut, Func},
     reply::Reply,
 };
 
@@ -22,6 +25,18 @@ where
     }
 }
 
+impl<E, F> IntoListener<'static, LocalAsyncListener<E>, E> for F
+where
+    E: 'static,
+    F: Func<E> + 'static,
+    F::Output: core::future::Future + 'static,
+    <F::Output as core::future::Future>::Output: Reply,
+{
+    fn into_listener(self) -> LocalAsyncListener<E> {
+        LocalAsyncListener::new(self)
+    }
+}
+
 #[cfg(feature = "sync")]
 impl<E, F> IntoListener<'static, SyncListener<E>, E> for F
 where
@@ -30,6 +45,19 @@ where
     F::Output: Reply,
 {
     fn into_listener(self) -> SyncListener<E> {
-        SyncListener::new(self.map(|m: F::Output| m.carry_on()))
+        SyncListener::new(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<E, F> IntoListener<'static, AsyncListener<E>, E> for F
+where
+    E: 'static,
+    F: Func<E> + Send + Sync + 'static,
+    F::Output: core::future::Future + Send + 'static,
+    <F::Output as core::future::Future>::Output: Reply,
+{
+    fn into_listener(self) -> AsyncListener<E> {
+        AsyncListener::new(self)
     }
 }
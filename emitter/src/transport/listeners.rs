@@ -0,0 +1,229 @@
+use core::any::Any;
+use std::{
+    collections::VecDeque,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    sync::Arc,
+};
+
+use generational_arena::{Arena, Index};
+
+use super::{
+    panic::PanicHandler,
+    reply::Flow,
+    stats::{ListenerStats, Timing},
+};
+
+struct Entry<L> {
+    listener: L,
+    priority: i32,
+    timing: Arc<Timing>,
+}
+
+/// Listener storage which dispatches by descending priority and then by insertion order.
+pub(crate) struct Listeners<L> {
+    arena: Arena<Entry<L>>,
+    order: Vec<Index>,
+    panic: PanicHandler,
+}
+
+impl<L> Default for Listeners<L> {
+    fn default() -> Self {
+        Listeners {
+            arena: Arena::default(),
+            order: Vec::default(),
+            panic: PanicHandler::default(),
+        }
+    }
+}
+
+impl<L> Listeners<L> {
+    pub fn insert(&mut self, listener: L, priority: i32) -> Index {
+        let idx = self.arena.insert(Entry {
+            listener,
+            priority,
+            timing: Arc::default(),
+        });
+        let arena = &self.arena;
+        let pos = self
+            .order
+            .partition_point(|next| arena[*next].priority >= priority);
+        self.order.insert(pos, idx);
+        idx
+    }
+
+    pub fn remove(&mut self, idx: Index) -> Option<L> {
+        let entry = self.arena.remove(idx)?;
+        self.order.retain(|next| *next != idx);
+        Some(entry.listener)
+    }
+
+    pub fn remove_all(&mut self, indices: impl IntoIterator<Item = Index>) {
+        for idx in indices {
+            self.remove(idx);
+        }
+    }
+
+    pub fn contains(&self, idx: Index) -> bool {
+        self.arena.contains(idx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Timings of the listeners in dispatch order.
+    pub fn stats(&self) -> Vec<ListenerStats> {
+        self.order
+            .iter()
+            .map(|idx| {
+                let entry = &self.arena[*idx];
+                entry.timing.stats(entry.priority)
+            })
+            .collect()
+    }
+
+    pub fn set_panic_handler(&mut self, handler: PanicHandler) {
+        self.panic = handler;
+    }
+}
+
+impl<L: Clone> Listeners<L> {
+    /// Copy out the listeners, so they can be called without holding on to the storage.
+    pub fn snapshot(&self) -> Snapshot<L> {
+        Snapshot {
+            listeners: self
+                .order
+                .iter()
+                .map(|idx| {
+                    let entry = &self.arena[*idx];
+                    (*idx, entry.listener.clone(), entry.timing.clone())
+                })
+                .collect(),
+            panic: self.panic.clone(),
+        }
+    }
+
+    /// Like [`snapshot`](Listeners::snapshot), but with a snapshot per listener
+    /// so each can be dispatched on its own.
+    #[cfg(feature = "threadpool")]
+    pub fn split(&self) -> Vec<Snapshot<L>> {
+        self.order
+            .iter()
+            .map(|idx| {
+                let entry = &self.arena[*idx];
+                Snapshot {
+                    listeners: vec![(*idx, entry.listener.clone(), entry.timing.clone())],
+                    panic: self.panic.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The first panic of a dispatch which wasn't handled by a hook.
+#[derive(Default)]
+pub(crate) struct Unhandled(Option<Box<dyn Any + Send>>);
+
+impl Unhandled {
+    fn keep(&mut self, payload: Box<dyn Any + Send>) {
+        self.0.get_or_insert(payload);
+    }
+
+    /// Re-raise the panic, once the transport is ready for the next event.
+    pub fn resume(self) {
+        if let Some(payload) = self.0 {
+            resume_unwind(payload);
+        }
+    }
+}
+
+pub(crate) struct Snapshot<L> {
+    listeners: Vec<(Index, L, Arc<Timing>)>,
+    panic: PanicHandler,
+}
+
+impl<L> Snapshot<L> {
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &L)> {
+        self.listeners
+            .iter()
+            .map(|(idx, listener, _)| (*idx, listener))
+    }
+
+    /// Call every listener in order and return the ones which unsubscribed.
+    /// A panicking listener is reported to the panic handler and doesn't
+    /// keep the event from the remaining listeners. Panics the handler didn't
+    /// handle are kept in `unhandled`.
+    pub fn dispatch<F>(&self, mut func: F, unhandled: &mut Unhandled) -> Vec<Index>
+    where
+        F: FnMut(&L) -> Flow,
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("dispatch", listeners = self.listeners.len()).entered();
+
+        let mut unsubscribed = Vec::new();
+
+        for (idx, listener, timing) in &self.listeners {
+            let ret = timing.time(|| catch_unwind(AssertUnwindSafe(|| func(listener))));
+
+            let flow = match ret {
+                Ok(flow) => flow,
+                Err(payload) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("listener panicked");
+                    let (remove, panic) = self.panic.report(payload);
+                    if let Some(panic) = panic {
+                        unhandled.keep(panic);
+                    }
+                    match remove {
+                        true => Flow::Unsubscribe,
+                        false => Flow::Continue,
+                    }
+                }
+            };
+
+            match flow {
+                Flow::Continue => {}
+                Flow::Stop => break,
+                Flow::Unsubscribe => unsubscribed.push(*idx),
+            }
+        }
+
+        unsubscribed
+    }
+}
+
+/// Events waiting to be dispatched. Events triggered while a dispatch is
+/// running are queued and delivered once the current event is done.
+pub(crate) struct Dispatch<E> {
+    dispatching: bool,
+    queue: VecDeque<E>,
+}
+
+impl<E> Default for Dispatch<E> {
+    fn default() -> Self {
+        Dispatch {
+            dispatching: false,
+            queue: VecDeque::default(),
+        }
+    }
+}
+
+impl<E> Dispatch<E> {
+    /// Queue the event. Returns true if the caller should start dispatching.
+    pub fn enqueue(&mut self, event: E) -> bool {
+        self.queue.push_back(event);
+        !std::mem::replace(&mut self.dispatching, true)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn next(&mut self) -> Option<E> {
+        let next = self.queue.pop_front();
+        if next.is_none() {
+            self.dispatching = false;
+        }
+        next
+    }
+}
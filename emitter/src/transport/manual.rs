@@ -0,0 +1,260 @@
+use core::time::Duration;
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use generational_arena::Index;
+use state::{Downgrade, State, StateTrait, WeakState};
+
+use crate::transport::{
+    func::CallbackMut,
+    listeners::{Listeners, Unhandled},
+    reply::Flow,
+    DirectListener, IntoListener, ListenerStats, PanicHandler, Subscription, Transport,
+};
+
+/// A listener call made by a [`Manual`] transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dispatched {
+    /// The position of the event in [`Manual::events`].
+    pub event: usize,
+    /// The listener, numbered in the order the listeners were added.
+    pub listener: usize,
+    pub flow: Flow,
+}
+
+struct Recorded<'a, E> {
+    id: usize,
+    listener: DirectListener<'a, E>,
+}
+
+type SharedListener<'a, E> = Rc<RefCell<Recorded<'a, E>>>;
+
+struct Log<E> {
+    queue: VecDeque<(usize, Rc<E>)>,
+    events: Vec<Rc<E>>,
+    dispatches: Vec<Dispatched>,
+    listeners: usize,
+}
+
+/// A transport for tests which queues events until they are dispatched
+/// with [`step`](Manual::step) or [`run_until_idle`](Manual::run_until_idle),
+/// and records every event and listener call.
+pub struct Manual<'a, E> {
+    listener: State<Listeners<SharedListener<'a, E>>>,
+    log: State<Log<E>>,
+}
+
+impl<'a, E> Clone for Manual<'a, E> {
+    fn clone(&self) -> Self {
+        Manual {
+            listener: self.listener.clone(),
+            log: self.log.clone(),
+        }
+    }
+}
+
+impl<'a, E> Default for Manual<'a, E> {
+    fn default() -> Self {
+        Manual {
+            listener: State::new(Listeners::default()),
+            log: State::new(Log {
+                queue: VecDeque::new(),
+                events: Vec::new(),
+                dispatches: Vec::new(),
+                listeners: 0,
+            }),
+        }
+    }
+}
+
+impl<'a, E> Manual<'a, E> {
+    pub fn new() -> Manual<'a, E> {
+        Manual::default()
+    }
+
+    /// Dispatch the next queued event. Returns false if the queue was empty.
+    pub fn step(&self) -> bool {
+        let (seq, event) = match self.log.write(|log| log.queue.pop_front()).unwrap() {
+            Some(next) => next,
+            None => return false,
+        };
+
+        let snapshot = match self.listener.read(|arena| arena.snapshot()) {
+            Ok(snapshot) => snapshot,
+            Err(_) => return true,
+        };
+
+        let mut unhandled = Unhandled::default();
+        let unsubscribed = snapshot.dispatch(
+            |next| {
+                let mut next = next.borrow_mut();
+                let flow = next.listener.call_mut(&event);
+                let listener = next.id;
+                self.log
+                    .write(|log| {
+                        log.dispatches.push(Dispatched {
+                            event: seq,
+                            listener,
+                            flow,
+                        })
+                    })
+                    .ok();
+                flow
+            },
+            &mut unhandled,
+        );
+
+        self.listener
+            .write(|arena| arena.remove_all(unsubscribed))
+            .ok();
+
+        unhandled.resume();
+        true
+    }
+
+    /// Dispatch queued events, including the ones emitted meanwhile, until the queue is empty.
+    /// Returns the number of events dispatched.
+    pub fn run_until_idle(&self) -> usize {
+        let mut count = 0;
+        while self.step() {
+            count += 1;
+        }
+        count
+    }
+
+    /// The number of queued events.
+    pub fn queued(&self) -> usize {
+        self.log.read(|log| log.queue.len()).unwrap_or(0)
+    }
+
+    /// Every event emitted so far, dispatched or not.
+    pub fn events(&self) -> Vec<E>
+    where
+        E: Clone,
+    {
+        self.log
+            .read(|log| log.events.iter().map(|event| (**event).clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Every listener call so far, in the order they were made.
+    pub fn dispatches(&self) -> Vec<Dispatched> {
+        self.log
+            .read(|log| log.dispatches.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl<'a, E> Transport<'a, E> for Manual<'a, E> {
+    type Listener = DirectListener<'a, E>;
+
+    type Subscription = ManualSubscription<'a, E>;
+
+    fn create_listener<L>(&self, listener: L) -> Self::Subscription
+    where
+        L: IntoListener<'a, Self::Listener, E>,
+    {
+        self.create_listener_with_priority(listener, 0)
+    }
+
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
+    where
+        L: IntoListener<'a, Self::Listener, E>,
+    {
+        let id = self
+            .log
+            .write(|log| {
+                log.listeners += 1;
+                log.listeners - 1
+            })
+            .unwrap();
+        let listener = Rc::new(RefCell::new(Recorded {
+            id,
+            listener: listener.into_listener(),
+        }));
+        let idx = self
+            .listener
+            .write(|arena| arena.insert(listener, priority))
+            .unwrap();
+
+        ManualSubscription {
+            idx: Some(idx),
+            arena: self.listener.downgrade(),
+        }
+    }
+
+    fn trigger(&self, event: E) {
+        self.log
+            .write(|log| {
+                let event = Rc::new(event);
+                log.queue.push_back((log.events.len(), event.clone()));
+                log.events.push(event);
+            })
+            .ok();
+    }
+
+    fn set_panic_handler(&self, handler: PanicHandler) {
+        self.listener
+            .write(|arena| arena.set_panic_handler(handler))
+            .ok();
+    }
+
+    fn listener_count(&self) -> usize {
+        self.listener.read(|arena| arena.len()).unwrap_or(0)
+    }
+
+    fn pending(&self) -> usize {
+        self.queued()
+    }
+
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.listener
+            .read(|arena| arena.stats())
+            .unwrap_or_default()
+    }
+
+    /// Runs every queued event, like [`run_until_idle`](Manual::run_until_idle).
+    fn flush(&self) {
+        self.run_until_idle();
+    }
+
+    fn flush_timeout(&self, _timeout: Duration) -> bool {
+        self.run_until_idle();
+        true
+    }
+}
+
+pub struct ManualSubscription<'a, E> {
+    arena: WeakState<Listeners<SharedListener<'a, E>>>,
+    idx: Option<Index>,
+}
+
+impl<'a, E> Subscription for ManualSubscription<'a, E> {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn detach(mut self) {
+        self.idx = None;
+    }
+
+    fn is_closed(&self) -> bool {
+        match self.idx {
+            Some(idx) => !self
+                .arena
+                .read(|arena| arena.contains(idx))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+impl<'a, E> Drop for ManualSubscription<'a, E> {
+    fn drop(&mut self) {
+        let idx = match self.idx.take() {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        self.arena.write(|arena| arena.remove(idx)).ok();
+    }
+}
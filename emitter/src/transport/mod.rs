@@ -1,8 +1,37 @@
+use core::time::Duration;
+
+mod combinators;
 mod func;
 mod into_listener;
+mod listeners;
+mod manual;
+mod panic;
+#[cfg(any(feature = "thread", feature = "threadpool"))]
+mod pending;
 mod reply;
+mod set;
+mod stats;
+mod timer;
+mod try_send;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use self::{combinators::Throttle, timer::ThreadTimer};
+pub use self::{
+    combinators::{Debounce, DebounceJob, Filter, FilterMap, OwnedBy, Take, WeakListener},
+    func::*,
+    manual::{Dispatched, Manual, ManualSubscription},
+    panic::{ListenerPanic, PanicHandler, PanicPolicy},
+    reply::{Flow, Reply},
+    set::{BoxSubscription, SubscriptionSet},
+    stats::ListenerStats,
+    timer::{Job, ManualTimer, Timer},
+    try_send::*,
+};
 
-mod r#async;
+#[cfg(feature = "async")]
+pub mod r#async;
+#[cfg(feature = "async")]
+pub use r#async::Async;
 
 pub mod direct;
 pub use direct::*;
@@ -10,18 +39,33 @@ pub use direct::*;
 #[cfg(feature = "thread")]
 pub mod thread;
 #[cfg(feature = "thread")]
-pub use thread::Thread;
+pub use thread::{Overflow, Thread};
+
+#[cfg(feature = "sync")]
+pub mod replay;
+#[cfg(feature = "sync")]
+pub use replay::Replay;
 
 #[cfg(feature = "threadpool")]
 pub mod pool;
+#[cfg(feature = "rayon")]
+pub use pool::Rayon;
 #[cfg(feature = "threadpool")]
-pub use pool::Pool;
+pub use pool::{FanOut, FanOutPool, Pool};
+
+#[cfg(all(unix, feature = "unix"))]
+pub mod unix;
+#[cfg(all(unix, feature = "unix"))]
+pub use unix::Unix;
 
 pub trait Listener<'a, E> {}
 
 pub trait Subscription {
     fn close(self);
     fn detach(self);
+    /// Whether the listener has been removed from the transport,
+    /// eg. because it returned `false` or an error.
+    fn is_closed(&self) -> bool;
 }
 
 pub trait IntoListener<'a, L, E>
@@ -39,5 +83,65 @@ pub trait Transport<'a, E> {
     where
         L: IntoListener<'a, Self::Listener, E>;
 
+    /// Listeners with a higher priority are called first. Transports which
+    /// don't support ordering ignore the priority.
+    fn create_listener_with_priority<L>(&self, listener: L, _priority: i32) -> Self::Subscription
+    where
+        L: IntoListener<'a, Self::Listener, E>,
+    {
+        self.create_listener(listener)
+    }
+
     fn trigger(&self, event: E);
+
+    /// Set how panicking listeners are handled. Transports which
+    /// don't isolate listener panics ignore the handler.
+    fn set_panic_handler(&self, _handler: PanicHandler) {}
+
+    fn try_trigger(&self, event: E) -> Result<TrySend<E>, TrySendError<E>> {
+        self.trigger(event);
+        Ok(TrySend::Sent)
+    }
+
+    /// The number of registered listeners. Transports which
+    /// don't keep track of their listeners return 0.
+    fn listener_count(&self) -> usize {
+        0
+    }
+
+    /// The number of events triggered but not dispatched yet.
+    fn pending(&self) -> usize {
+        0
+    }
+
+    /// How many events the transport queues before its overflow policy applies.
+    /// Transports which don't queue events or don't bound the queue return `None`.
+    fn queue_capacity(&self) -> Option<usize> {
+        None
+    }
+
+    /// How often and how long each listener has been called, in dispatch order.
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        Vec::new()
+    }
+
+    /// Block until every event triggered so far has been dispatched to the listeners.
+    fn flush(&self) {}
+
+    /// Like [`flush`](Transport::flush), but gives up after `timeout`.
+    /// Returns false if the timeout elapsed.
+    fn flush_timeout(&self, _timeout: Duration) -> bool {
+        true
+    }
+
+    /// Stop accepting events, dispatch the pending ones and join any worker threads.
+    fn shutdown(&self) {
+        self.flush()
+    }
+
+    /// Like [`shutdown`](Transport::shutdown), but gives up after `timeout`.
+    /// Returns false if the timeout elapsed.
+    fn shutdown_timeout(&self, timeout: Duration) -> bool {
+        self.flush_timeout(timeout)
+    }
 }
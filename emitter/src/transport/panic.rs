@@ -0,0 +1,117 @@
+use core::{any::Any, fmt};
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+};
+
+/// What happens to a listener after it panicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Keep the listener around for the next event.
+    #[default]
+    Keep,
+    /// Remove the listener, as if it had returned `false`.
+    Remove,
+}
+
+/// A panic caught while calling a listener.
+pub struct ListenerPanic {
+    payload: Box<dyn Any + Send>,
+    removed: bool,
+}
+
+impl ListenerPanic {
+    pub(crate) fn new(payload: Box<dyn Any + Send>, removed: bool) -> ListenerPanic {
+        ListenerPanic { payload, removed }
+    }
+
+    /// The panic message, if the listener panicked with a string.
+    pub fn message(&self) -> Option<&str> {
+        if let Some(msg) = self.payload.downcast_ref::<&'static str>() {
+            Some(msg)
+        } else {
+            self.payload
+                .downcast_ref::<String>()
+                .map(|msg| msg.as_str())
+        }
+    }
+
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        &*self.payload
+    }
+
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+
+    /// Whether the listener was removed because of the panic.
+    pub fn removed(&self) -> bool {
+        self.removed
+    }
+}
+
+impl fmt::Debug for ListenerPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ListenerPanic")
+            .field("message", &self.message())
+            .field("removed", &self.removed)
+            .finish()
+    }
+}
+
+type PanicHook = Arc<dyn Fn(&ListenerPanic) + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub struct PanicHandler {
+    policy: PanicPolicy,
+    hook: Option<PanicHook>,
+}
+
+impl PanicHandler {
+    pub fn new(policy: PanicPolicy) -> PanicHandler {
+        PanicHandler { policy, hook: None }
+    }
+
+    pub fn with_hook<F>(policy: PanicPolicy, hook: F) -> PanicHandler
+    where
+        F: Fn(&ListenerPanic) + Send + Sync + 'static,
+    {
+        PanicHandler {
+            policy,
+            hook: Some(Arc::new(hook)),
+        }
+    }
+
+    pub fn policy(&self) -> PanicPolicy {
+        self.policy
+    }
+
+    /// Report a caught panic and return whether the listener should be removed,
+    /// along with the panic to re-raise once the dispatch is done.
+    ///
+    /// Without a hook that is the listener's panic, so it still reaches the caller,
+    /// and with one it is the hook's own panic, if any.
+    pub(crate) fn report(
+        &self,
+        payload: Box<dyn Any + Send>,
+    ) -> (bool, Option<Box<dyn Any + Send>>) {
+        let remove = self.policy == PanicPolicy::Remove;
+        let unhandled = match &self.hook {
+            Some(hook) => {
+                let panic = ListenerPanic::new(payload, remove);
+                catch_unwind(AssertUnwindSafe(|| hook(&panic))).err()
+            }
+            None => Some(payload),
+        };
+        (remove, unhandled)
+    }
+}
+
+impl fmt::Debug for PanicHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicHandler")
+            .field("policy", &self.policy)
+            .field("hook", &self.hook.is_some())
+            .finish()
+    }
+}
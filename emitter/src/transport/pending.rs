@@ -0,0 +1,67 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Counts events which have been queued but not yet dispatched.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Pending {
+    inner: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl Pending {
+    pub fn inc(&self) {
+        *self.inner.0.lock().expect("lock") += 1;
+    }
+
+    pub fn dec(&self) {
+        let (lock, cvar) = &*self.inner;
+        let mut count = lock.lock().expect("lock");
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            cvar.notify_all();
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        *self.inner.0.lock().expect("lock")
+    }
+
+    /// Counts an event as pending until the returned guard is dropped.
+    #[cfg(feature = "threadpool")]
+    pub fn enter(&self) -> PendingGuard {
+        self.inc();
+        PendingGuard(self.clone())
+    }
+
+    pub fn wait(&self) {
+        let (lock, cvar) = &*self.inner;
+        let count = lock.lock().expect("lock");
+        drop(cvar.wait_while(count, |count| *count > 0).expect("lock"));
+    }
+
+    /// Returns false if the timeout elapsed before every event was dispatched.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let (lock, cvar) = &*self.inner;
+        let deadline = Instant::now() + timeout;
+        let mut count = lock.lock().expect("lock");
+        while *count > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            count = cvar.wait_timeout(count, deadline - now).expect("lock").0;
+        }
+        true
+    }
+}
+
+#[cfg(feature = "threadpool")]
+pub(crate) struct PendingGuard(Pending);
+
+#[cfg(feature = "threadpool")]
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use threadpool::ThreadPool;
+
+/// Runs the jobs of a [`Pool`](super::Pool) or [`FanOutPool`](super::FanOutPool).
+pub trait Executor: Send + Sync + 'static {
+    fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static;
+
+    /// Block until all submitted jobs have finished.
+    ///
+    /// The pools wait for their own events before joining,
+    /// so executors which can't join may leave this empty.
+    fn join(&self) {}
+}
+
+impl<X> Executor for Arc<X>
+where
+    X: Executor,
+{
+    fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        (**self).execute(job)
+    }
+
+    fn join(&self) {
+        (**self).join()
+    }
+}
+
+/// A Send + Sync thread pool.
+///
+/// Jobs are submitted through the pool's channel without taking a lock.
+#[derive(Clone)]
+pub struct SyncThreadPool {
+    pool: ThreadPool,
+}
+
+impl SyncThreadPool {
+    /// Create a new thread pool with the specified size.
+    pub fn new(num_threads: usize) -> Self {
+        Self {
+            pool: ThreadPool::new(num_threads),
+        }
+    }
+
+    /// Execute a job on the thread pool.
+    pub fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        self.pool.execute(job);
+    }
+
+    /// Block until all jobs submitted to the thread pool have finished.
+    pub fn join(&self) {
+        self.pool.join();
+    }
+}
+
+impl Default for SyncThreadPool {
+    fn default() -> Self {
+        SyncThreadPool::new(num_cpus::get())
+    }
+}
+
+impl From<ThreadPool> for SyncThreadPool {
+    fn from(th: ThreadPool) -> Self {
+        SyncThreadPool { pool: th }
+    }
+}
+
+impl Executor for SyncThreadPool {
+    fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        SyncThreadPool::execute(self, job)
+    }
+
+    fn join(&self) {
+        SyncThreadPool::join(self)
+    }
+}
+
+/// Runs jobs on a rayon thread pool, or on rayon's global pool by default.
+#[cfg(feature = "rayon")]
+#[derive(Debug, Clone, Default)]
+pub struct Rayon {
+    pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+#[cfg(feature = "rayon")]
+impl Rayon {
+    /// Create a new rayon thread pool with the specified size.
+    pub fn new(num_threads: usize) -> Result<Rayon, rayon::ThreadPoolBuildError> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()?;
+        Ok(Rayon::from(pool))
+    }
+
+    /// Use rayon's global thread pool.
+    pub fn global() -> Rayon {
+        Rayon { pool: None }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl From<rayon::ThreadPool> for Rayon {
+    fn from(pool: rayon::ThreadPool) -> Self {
+        Rayon {
+            pool: Some(Arc::new(pool)),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl From<Arc<rayon::ThreadPool>> for Rayon {
+    fn from(pool: Arc<rayon::ThreadPool>) -> Self {
+        Rayon { pool: Some(pool) }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Executor for Rayon {
+    fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        match &self.pool {
+            Some(pool) => pool.spawn(job),
+            None => rayon::spawn(job),
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use core::time::Duration;
+use std::{
+    collections::VecDeque,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use generational_arena::Index;
+use state::{Downgrade, MutexState, StateTrait, WeakMutexState};
+
+use super::{
+    listeners::{Listeners, Snapshot, Unhandled},
+    pending::{Pending, PendingGuard},
+};
+
+mod executor;
+
+pub use self::executor::*;
+use crate::{
+    direct::sync::{SyncDirectSubscription, SyncListener},
+    Callback, IntoListener, ListenerStats, PanicHandler, Subscription, SyncDirect, Transport,
+    TrySend, TrySendError,
+};
+
+pub struct Pool<E: 'static, X = SyncThreadPool> {
+    thpool: X,
+    trigger: SyncDirect<E>,
+    pending: Pending,
+    closed: Arc<AtomicBool>,
+}
+
+impl<E, X: Clone> Clone for Pool<E, X> {
+    fn clone(&self) -> Self {
+        Pool {
+            thpool: self.thpool.clone(),
+            trigger: self.trigger.clone(),
+            pending: self.pending.clone(),
+            closed: self.closed.clone(),
+        }
+    }
+}
+
+impl<E, X: Default> Default for Pool<E, X> {
+    fn default() -> Self {
+        Pool::with_executor(X::default())
+    }
+}
+
+impl<E> Pool<E> {
+    pub fn new(num: usize) -> Pool<E> {
+        Pool::new_with(SyncThreadPool::new(num))
+    }
+
+    pub fn new_with(thpool: impl Into<SyncThreadPool>) -> Pool<E> {
+        Pool::with_executor(thpool.into())
+    }
+}
+
+impl<E, X> Pool<E, X> {
+    /// Run events on any [`Executor`], eg. [`Rayon`].
+    pub fn with_executor(executor: X) -> Pool<E, X> {
+        Pool {
+            thpool: executor,
+            trigger: SyncDirect::default(),
+            pending: Pending::default(),
+            closed: Arc::default(),
+        }
+    }
+
+    pub fn executor(&self) -> &X {
+        &self.thpool
+    }
+}
+
+impl<E, X> Transport<'static, E> for Pool<E, X>
+where
+    E: Send + 'static,
+    X: Executor,
+{
+    type Listener = SyncListener<E>;
+
+    type Subscription = SyncDirectSubscription<E>;
+
+    fn create_listener<L>(&self, listener: L) -> Self::Subscription
+    where
+        L: crate::IntoListener<'static, Self::Listener, E>,
+    {
+        self.trigger.create_listener(listener)
+    }
+
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
+    where
+        L: crate::IntoListener<'static, Self::Listener, E>,
+    {
+        self.trigger
+            .create_listener_with_priority(listener, priority)
+    }
+
+    fn trigger(&self, event: E) {
+        self.try_trigger(event).ok();
+    }
+
+    fn try_trigger(&self, event: E) -> Result<TrySend<E>, TrySendError<E>> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(TrySendError::Disconnected(event));
+        }
+
+        let trigger = self.trigger.clone();
+        let guard = self.pending.enter();
+        self.thpool.execute(move || {
+            // Unhandled listener panics are re-raised, which mustn't take down the executor
+            catch_unwind(AssertUnwindSafe(|| trigger.trigger(event))).ok();
+            drop(guard);
+        });
+
+        Ok(TrySend::Sent)
+    }
+
+    fn set_panic_handler(&self, handler: PanicHandler) {
+        self.trigger.set_panic_handler(handler)
+    }
+
+    fn listener_count(&self) -> usize {
+        self.trigger.listener_count()
+    }
+
+    fn pending(&self) -> usize {
+        self.pending.count()
+    }
+
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.trigger.listener_stats()
+    }
+
+    fn flush(&self) {
+        self.pending.wait();
+    }
+
+    fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.pending.wait_timeout(timeout)
+    }
+
+    fn shutdown(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.pending.wait();
+        self.thpool.join();
+    }
+
+    fn shutdown_timeout(&self, timeout: Duration) -> bool {
+        self.closed.store(true, Ordering::Release);
+        if !self.pending.wait_timeout(timeout) {
+            return false;
+        }
+        self.thpool.join();
+        true
+    }
+}
+
+/// The order in which a [`FanOutPool`] listener sees events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FanOut {
+    /// Every listener call is its own job, so a listener may see events out of order.
+    #[default]
+    Unordered,
+    /// A listener sees events in the order they were emitted, one at a time.
+    Fifo,
+}
+
+struct Job<E: 'static> {
+    event: Arc<E>,
+    snapshot: Snapshot<Arc<Slot<E>>>,
+    _pending: Arc<PendingGuard>,
+}
+
+impl<E> Job<E> {
+    fn run(self, arena: &WeakMutexState<Listeners<Arc<Slot<E>>>>) {
+        // Skip listeners which were removed after the event was emitted
+        let live = self
+            .snapshot
+            .iter()
+            .all(|(idx, _)| arena.read(|arena| arena.contains(idx)).unwrap_or(false));
+        if !live {
+            return;
+        }
+
+        // There's no caller to re-raise to, the panic hook already reported the panic
+        let unsubscribed = self.snapshot.dispatch(
+            |slot| slot.listener.call(&self.event),
+            &mut Unhandled::default(),
+        );
+        if !unsubscribed.is_empty() {
+            arena.write(|arena| arena.remove_all(unsubscribed)).ok();
+        }
+    }
+}
+
+struct Queue<E: 'static> {
+    jobs: VecDeque<Job<E>>,
+    running: bool,
+}
+
+struct Slot<E: 'static> {
+    listener: SyncListener<E>,
+    // Only used for `FanOut::Fifo`
+    queue: Mutex<Queue<E>>,
+}
+
+impl<E> Slot<E> {
+    fn drain(&self, arena: &WeakMutexState<Listeners<Arc<Slot<E>>>>) {
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().expect("lock");
+                match queue.jobs.pop_front() {
+                    Some(job) => job,
+                    None => {
+                        queue.running = false;
+                        return;
+                    }
+                }
+            };
+            job.run(arena);
+        }
+    }
+}
+
+/// A pool transport which runs every listener as its own job,
+/// so slow listeners don't hold up the others.
+///
+/// Events are shared between the jobs behind an `Arc`. Priorities only decide
+/// the order the jobs are submitted in and [`Flow::Stop`](crate::Flow::Stop) has no effect.
+pub struct FanOutPool<E: 'static, X = SyncThreadPool> {
+    thpool: X,
+    listener: MutexState<Listeners<Arc<Slot<E>>>>,
+    order: FanOut,
+    pending: Pending,
+    closed: Arc<AtomicBool>,
+}
+
+impl<E, X: Clone> Clone for FanOutPool<E, X> {
+    fn clone(&self) -> Self {
+        FanOutPool {
+            thpool: self.thpool.clone(),
+            listener: self.listener.clone(),
+            order: self.order,
+            pending: self.pending.clone(),
+            closed: self.closed.clone(),
+        }
+    }
+}
+
+impl<E, X: Default> Default for FanOutPool<E, X> {
+    fn default() -> Self {
+        FanOutPool::with_executor(X::default(), FanOut::default())
+    }
+}
+
+impl<E> FanOutPool<E> {
+    pub fn new(num: usize, order: FanOut) -> FanOutPool<E> {
+        FanOutPool::new_with(SyncThreadPool::new(num), order)
+    }
+
+    pub fn new_with(thpool: impl Into<SyncThreadPool>, order: FanOut) -> FanOutPool<E> {
+        FanOutPool::with_executor(thpool.into(), order)
+    }
+}
+
+impl<E, X> FanOutPool<E, X> {
+    pub fn with_executor(executor: X, order: FanOut) -> FanOutPool<E, X> {
+        FanOutPool {
+            thpool: executor,
+            listener: MutexState::new(Listeners::default()),
+            order,
+            pending: Pending::default(),
+            closed: Arc::default(),
+        }
+    }
+
+    pub fn order(&self) -> FanOut {
+        self.order
+    }
+}
+
+impl<E, X> Transport<'static, E> for FanOutPool<E, X>
+where
+    E: Send + Sync + 'static,
+    X: Executor,
+{
+    type Listener = SyncListener<E>;
+
+    type Subscription = FanOutSubscription<E>;
+
+    fn create_listener<L>(&self, listener: L) -> Self::Subscription
+    where
+        L: IntoListener<'static, Self::Listener, E>,
+    {
+        self.create_listener_with_priority(listener, 0)
+    }
+
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
+    where
+        L: IntoListener<'static, Self::Listener, E>,
+    {
+        let slot = Arc::new(Slot {
+            listener: listener.into_listener(),
+            queue: Mutex::new(Queue {
+                jobs: VecDeque::new(),
+                running: false,
+            }),
+        });
+        let idx = self
+            .listener
+            .write(move |listeners| listeners.insert(slot, priority))
+            .unwrap();
+
+        FanOutSubscription {
+            idx: Some(idx),
+            arena: self.listener.downgrade(),
+        }
+    }
+
+    fn trigger(&self, event: E) {
+        self.try_trigger(event).ok();
+    }
+
+    fn try_trigger(&self, event: E) -> Result<TrySend<E>, TrySendError<E>> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(TrySendError::Disconnected(event));
+        }
+
+        let snapshots = match self.listener.read(|arena| arena.split()) {
+            Ok(snapshots) => snapshots,
+            Err(_) => return Ok(TrySend::Sent),
+        };
+
+        let event = Arc::new(event);
+        let pending = Arc::new(self.pending.enter());
+
+        for snapshot in snapshots {
+            let slot = match snapshot.iter().next() {
+                Some((_, slot)) => slot.clone(),
+                None => continue,
+            };
+            let job = Job {
+                event: event.clone(),
+                snapshot,
+                _pending: pending.clone(),
+            };
+            let arena = self.listener.downgrade();
+
+            match self.order {
+                FanOut::Unordered => self.thpool.execute(move || job.run(&arena)),
+                FanOut::Fifo => {
+                    let start = {
+                        let mut queue = slot.queue.lock().expect("lock");
+                        queue.jobs.push_back(job);
+                        !core::mem::replace(&mut queue.running, true)
+                    };
+                    if start {
+                        self.thpool.execute(move || slot.drain(&arena));
+                    }
+                }
+            }
+        }
+
+        Ok(TrySend::Sent)
+    }
+
+    fn set_panic_handler(&self, handler: PanicHandler) {
+        self.listener
+            .write(|arena| arena.set_panic_handler(handler))
+            .ok();
+    }
+
+    fn listener_count(&self) -> usize {
+        self.listener.read(|arena| arena.len()).unwrap_or(0)
+    }
+
+    fn pending(&self) -> usize {
+        self.pending.count()
+    }
+
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.listener
+            .read(|arena| arena.stats())
+            .unwrap_or_default()
+    }
+
+    fn flush(&self) {
+        self.pending.wait();
+    }
+
+    fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.pending.wait_timeout(timeout)
+    }
+
+    fn shutdown(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.pending.wait();
+        self.thpool.join();
+    }
+
+    fn shutdown_timeout(&self, timeout: Duration) -> bool {
+        self.closed.store(true, Ordering::Release);
+        if !self.pending.wait_timeout(timeout) {
+            return false;
+        }
+        self.thpool.join();
+        true
+    }
+}
+
+pub struct FanOutSubscription<E: 'static> {
+    arena: WeakMutexState<Listeners<Arc<Slot<E>>>>,
+    idx: Option<Index>,
+}
+
+impl<E> Subscription for FanOutSubscription<E> {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn detach(mut self) {
+        self.idx = None;
+    }
+
+    fn is_closed(&self) -> bool {
+        match self.idx {
+            Some(idx) => !self
+                .arena
+                .read(|arena| arena.contains(idx))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+impl<E> Drop for FanOutSubscription<E> {
+    fn drop(&mut self) {
+        let idx = match self.idx.take() {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        self.arena.write(|arena| arena.remove(idx)).ok();
+    }
+}
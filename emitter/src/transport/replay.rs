@@ -0,0 +1,286 @@
+use core::time::Duration;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::ReentrantMutex;
+
+use crate::{
+    transport::{
+        func::{Callback, CallbackMut},
+        reply::{Flow, Reply},
+    },
+    Emitter, IntoListener, ListenerStats, PanicHandler, Transport, TrySend, TrySendError,
+};
+
+struct History<E> {
+    // Numbered, so an event can be forgotten again after newer ones were pushed
+    events: VecDeque<(u64, E)>,
+    next: u64,
+    capacity: usize,
+}
+
+impl<E> History<E> {
+    fn push(&mut self, event: E) -> u64 {
+        let id = self.next;
+        self.next += 1;
+        if self.capacity == 0 {
+            return id;
+        }
+        if self.events.len() >= self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back((id, event));
+        id
+    }
+
+    fn remove(&mut self, id: u64) {
+        if let Some(index) = self.events.iter().position(|(other, _)| *other == id) {
+            self.events.remove(index);
+        }
+    }
+
+    // The queue evicting its oldest event held the last `queued` accepted events,
+    // which are also the latest remembered ones, before the event with `id`
+    fn evict(&mut self, id: u64, queued: usize) {
+        let Some(index) = self.events.iter().position(|(other, _)| *other == id) else {
+            return;
+        };
+        if let Some(index) = index.checked_sub(queued) {
+            self.events.remove(index);
+        }
+    }
+
+    fn cloned(&self) -> Vec<E>
+    where
+        E: Clone,
+    {
+        self.events.iter().map(|(_, event)| event.clone()).collect()
+    }
+}
+
+/// A transport wrapper remembering the most recent events, so listeners added
+/// through [`Emitter::listen_with_replay`] see them before any new event.
+pub struct Replay<E, T> {
+    inner: T,
+    // Held while triggering, so a listener is either replayed an event or sees it live
+    history: Arc<ReentrantMutex<RefCell<History<E>>>>,
+}
+
+impl<E, T: Clone> Clone for Replay<E, T> {
+    fn clone(&self) -> Self {
+        Replay {
+            inner: self.inner.clone(),
+            history: self.history.clone(),
+        }
+    }
+}
+
+impl<E, T> Replay<E, T> {
+    /// Keep the last `capacity` events.
+    pub fn new(inner: T, capacity: usize) -> Replay<E, T> {
+        Replay {
+            inner,
+            history: Arc::new(ReentrantMutex::new(RefCell::new(History {
+                events: VecDeque::with_capacity(capacity),
+                next: 0,
+                capacity,
+            }))),
+        }
+    }
+
+    /// Only keep the last event, like a behavior subject.
+    pub fn latest(inner: T) -> Replay<E, T> {
+        Replay::new(inner, 1)
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.history.lock().borrow().capacity
+    }
+
+    /// The remembered events, oldest first.
+    pub fn events(&self) -> Vec<E>
+    where
+        E: Clone,
+    {
+        self.history.lock().borrow().cloned()
+    }
+
+    pub fn clear(&self) {
+        self.history.lock().borrow_mut().events.clear();
+    }
+
+    pub fn create_listener_with_replay<'a, L>(&self, listener: L, priority: i32) -> T::Subscription
+    where
+        E: Clone,
+        T: Transport<'a, E>,
+        L: CallbackMut<E>,
+        L::Output: Reply,
+        Replayed<L>: IntoListener<'a, T::Listener, E>,
+    {
+        let history = self.history.lock();
+        // Copied so the listener is free to emit while being replayed
+        let events = history.borrow().cloned();
+
+        let mut listener = listener;
+        let mut closed = false;
+        for event in events {
+            if listener.call_mut(&event).into_flow() == Flow::Unsubscribe {
+                closed = true;
+                break;
+            }
+        }
+
+        self.inner.create_listener_with_priority(
+            Replayed {
+                listener,
+                closed: AtomicBool::new(closed),
+            },
+            priority,
+        )
+    }
+}
+
+impl<'a, E, T> Transport<'a, E> for Replay<E, T>
+where
+    E: Clone,
+    T: Transport<'a, E>,
+{
+    type Listener = T::Listener;
+
+    type Subscription = T::Subscription;
+
+    fn create_listener<L>(&self, listener: L) -> Self::Subscription
+    where
+        L: IntoListener<'a, Self::Listener, E>,
+    {
+        self.inner.create_listener(listener)
+    }
+
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
+    where
+        L: IntoListener<'a, Self::Listener, E>,
+    {
+        self.inner.create_listener_with_priority(listener, priority)
+    }
+
+    /// Only events the inner transport accepted are remembered, and events it evicted
+    /// from its queue are forgotten again.
+    fn trigger(&self, event: E) {
+        self.try_trigger(event).ok();
+    }
+
+    fn try_trigger(&self, event: E) -> Result<TrySend<E>, TrySendError<E>> {
+        let history = self.history.lock();
+        // Remembered before triggering, so events emitted by the listeners come after it
+        let id = history.borrow_mut().push(event.clone());
+        let ret = self.inner.try_trigger(event);
+        match (&ret, self.inner.queue_capacity()) {
+            (Ok(TrySend::Sent), _) => {}
+            (Ok(TrySend::Evicted(_)), Some(queued)) => history.borrow_mut().evict(id, queued),
+            (Ok(TrySend::Evicted(_)), None) => {}
+            _ => history.borrow_mut().remove(id),
+        }
+        ret
+    }
+
+    fn listener_count(&self) -> usize {
+        self.inner.listener_count()
+    }
+
+    fn pending(&self) -> usize {
+        self.inner.pending()
+    }
+
+    fn queue_capacity(&self) -> Option<usize> {
+        self.inner.queue_capacity()
+    }
+
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.inner.listener_stats()
+    }
+
+    fn set_panic_handler(&self, handler: PanicHandler) {
+        self.inner.set_panic_handler(handler)
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+
+    fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.inner.flush_timeout(timeout)
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown()
+    }
+
+    fn shutdown_timeout(&self, timeout: Duration) -> bool {
+        self.inner.shutdown_timeout(timeout)
+    }
+}
+
+/// A listener which has been replayed the remembered events.
+pub struct Replayed<L> {
+    listener: L,
+    // Set when the listener unsubscribed during the replay
+    closed: AtomicBool,
+}
+
+impl<E, L> Callback<E> for Replayed<L>
+where
+    L: Callback<E>,
+    L::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call(&self, input: &E) -> Self::Output {
+        if self.closed.load(Ordering::Acquire) {
+            Flow::Unsubscribe
+        } else {
+            self.listener.call(input).into_flow()
+        }
+    }
+}
+
+impl<E, L> CallbackMut<E> for Replayed<L>
+where
+    L: CallbackMut<E>,
+    L::Output: Reply,
+{
+    type Output = Flow;
+
+    fn call_mut(&mut self, input: &E) -> Self::Output {
+        if *self.closed.get_mut() {
+            Flow::Unsubscribe
+        } else {
+            self.listener.call_mut(input).into_flow()
+        }
+    }
+}
+
+impl<'a, E, T> Emitter<E, Replay<E, T>>
+where
+    E: Clone,
+    T: Transport<'a, E>,
+{
+    /// Listen to events, receiving the events remembered by the transport first.
+    pub fn listen_with_replay<L>(&self, listener: L) -> T::Subscription
+    where
+        L: CallbackMut<E>,
+        L::Output: Reply,
+        Replayed<L>: IntoListener<'a, T::Listener, E>,
+    {
+        self.transport().create_listener_with_replay(listener, 0)
+    }
+}
@@ -1,5 +1,38 @@
+/// What happens after a listener has handled an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flow {
+    /// Keep the listener and pass the event on to the next one.
+    #[default]
+    Continue,
+    /// Keep the listener, but don't pass the event on to lower priority listeners.
+    Stop,
+    /// Remove the listener and pass the event on to the next one.
+    Unsubscribe,
+}
+
 pub trait Reply {
     fn carry_on(self) -> bool;
+
+    fn into_flow(self) -> Flow
+    where
+        Self: Sized,
+    {
+        if self.carry_on() {
+            Flow::Continue
+        } else {
+            Flow::Unsubscribe
+        }
+    }
+}
+
+impl Reply for Flow {
+    fn carry_on(self) -> bool {
+        self != Flow::Unsubscribe
+    }
+
+    fn into_flow(self) -> Flow {
+        self
+    }
 }
 
 impl Reply for bool {
@@ -16,9 +49,6 @@ impl Reply for () {
 
 impl<S, E> Reply for Result<S, E> {
     fn carry_on(self) -> bool {
-        match self {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        self.is_ok()
     }
 }
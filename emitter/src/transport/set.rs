@@ -0,0 +1,131 @@
+use super::Subscription;
+
+// `Subscription` consumes itself, so boxed subscriptions go through this
+trait DynSubscription {
+    fn close_box(self: Box<Self>);
+    fn detach_box(self: Box<Self>);
+    fn is_closed_box(&self) -> bool;
+}
+
+impl<S: Subscription> DynSubscription for S {
+    fn close_box(self: Box<Self>) {
+        (*self).close()
+    }
+
+    fn detach_box(self: Box<Self>) {
+        (*self).detach()
+    }
+
+    fn is_closed_box(&self) -> bool {
+        self.is_closed()
+    }
+}
+
+/// A type erased subscription of any transport.
+pub struct BoxSubscription<'a> {
+    inner: Box<dyn DynSubscription + 'a>,
+}
+
+impl<'a> BoxSubscription<'a> {
+    pub fn new<S: Subscription + 'a>(subscription: S) -> BoxSubscription<'a> {
+        BoxSubscription {
+            inner: Box::new(subscription),
+        }
+    }
+}
+
+impl<'a> Subscription for BoxSubscription<'a> {
+    fn close(self) {
+        self.inner.close_box()
+    }
+
+    fn detach(self) {
+        self.inner.detach_box()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed_box()
+    }
+}
+
+/// A group of subscriptions which are closed together when the set is dropped.
+///
+/// To end listeners when their owner goes away instead, wrap them with
+/// [`owned_by`](crate::CallbackExt::owned_by).
+#[derive(Default)]
+pub struct SubscriptionSet<'a> {
+    subscriptions: Vec<BoxSubscription<'a>>,
+}
+
+impl<'a> SubscriptionSet<'a> {
+    pub fn new() -> SubscriptionSet<'a> {
+        SubscriptionSet::default()
+    }
+
+    pub fn insert<S: Subscription + 'a>(&mut self, subscription: S) {
+        self.subscriptions.push(BoxSubscription::new(subscription));
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Drop the subscriptions whose listeners have already been removed.
+    pub fn prune(&mut self) {
+        self.subscriptions.retain(|next| !next.is_closed());
+    }
+
+    pub fn close_all(&mut self) {
+        for next in self.subscriptions.drain(..) {
+            next.close();
+        }
+    }
+
+    /// Keep every listener registered after the set is gone.
+    pub fn detach_all(mut self) {
+        for next in self.subscriptions.drain(..) {
+            next.detach();
+        }
+    }
+}
+
+impl<'a, S: Subscription + 'a> Extend<S> for SubscriptionSet<'a> {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for next in iter {
+            self.insert(next);
+        }
+    }
+}
+
+impl<'a, S: Subscription + 'a> FromIterator<S> for SubscriptionSet<'a> {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut set = SubscriptionSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a> Subscription for SubscriptionSet<'a> {
+    fn close(mut self) {
+        self.close_all()
+    }
+
+    fn detach(self) {
+        self.detach_all()
+    }
+
+    /// Whether every subscription in the set is closed.
+    fn is_closed(&self) -> bool {
+        self.subscriptions.iter().all(|next| next.is_closed())
+    }
+}
+
+impl<'a> Drop for SubscriptionSet<'a> {
+    fn drop(&mut self) {
+        self.close_all();
+    }
+}
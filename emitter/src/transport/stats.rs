@@ -0,0 +1,66 @@
+use core::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How often and how long a listener has been called.
+///
+/// On `wasm32-unknown-unknown` there is no clock, so only the calls are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ListenerStats {
+    pub priority: i32,
+    pub calls: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl ListenerStats {
+    pub fn mean(&self) -> Duration {
+        match self.calls {
+            0 => Duration::ZERO,
+            calls => Duration::from_nanos((self.total.as_nanos() / calls as u128) as u64),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Timing {
+    calls: AtomicU64,
+    total: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Timing {
+    /// Call `func` and record how long it took.
+    pub fn time<R>(&self, func: impl FnOnce() -> R) -> R {
+        // `Instant::now` panics on wasm32-unknown-unknown
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        {
+            let start = std::time::Instant::now();
+            let ret = func();
+            self.record(start.elapsed());
+            ret
+        }
+
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        {
+            let ret = func();
+            self.record(Duration::ZERO);
+            ret
+        }
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(nanos, Ordering::Relaxed);
+        self.max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self, priority: i32) -> ListenerStats {
+        ListenerStats {
+            priority,
+            calls: self.calls.load(Ordering::Relaxed),
+            total: Duration::from_nanos(self.total.load(Ordering::Relaxed)),
+            max: Duration::from_nanos(self.max.load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -1,22 +1,186 @@
-use std::thread::JoinHandle;
+use core::time::Duration;
+use std::{
+    collections::VecDeque,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::JoinHandle,
+};
 
-use crossbeam_channel::{unbounded, Sender};
-use state::{Downgrade, IntoInner, MutexState, WeakMutexState};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError};
+use state::{Downgrade, IntoInner, MutexState, StateTrait, WeakMutexState};
 
+use super::pending::Pending;
 use crate::{
     direct::sync::{SyncDirectSubscription, SyncListener},
-    Subscription, SyncDirect, Transport,
+    ListenerStats, PanicHandler, Subscription, SyncDirect, Transport, TrySend, TrySendError,
 };
 
 enum Message<E: 'static> {
     Event(E),
-    Listener(SyncListener<E>, WeakMutexState<SyncDirectSubscription<E>>),
+    Listener(
+        SyncListener<E>,
+        i32,
+        WeakMutexState<SyncDirectSubscription<E>>,
+    ),
+    PanicHandler(PanicHandler),
+    Shutdown,
+}
+
+/// What a bounded [`Thread`] does with an event when its queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Block the emitter until there is room in the queue.
+    #[default]
+    Block,
+    /// Drop the event being emitted.
+    DropNewest,
+    /// Drop the oldest pending event to make room.
+    DropOldest,
+    /// Reject the event. `try_emit` returns [`TrySendError::Full`], `emit` drops it.
+    Error,
+}
+
+struct Queue<E: 'static> {
+    messages: VecDeque<Message<E>>,
+    // Only events count against the capacity
+    events: usize,
+    // Events queued and taken out so far, for zero capacity queues to wait on the worker
+    queued: u64,
+    taken: u64,
+    disconnected: bool,
+}
+
+// Listeners go through the same queue as the events, so they only see the events emitted after them
+struct Channel<E: 'static> {
+    queue: Mutex<Queue<E>>,
+    cap: Option<usize>,
+    ready: Condvar,
+    room: Condvar,
+}
+
+impl<E> Channel<E> {
+    fn new(cap: Option<usize>) -> Channel<E> {
+        Channel {
+            queue: Mutex::new(Queue {
+                messages: VecDeque::new(),
+                events: 0,
+                queued: 0,
+                taken: 0,
+                disconnected: false,
+            }),
+            cap,
+            ready: Condvar::new(),
+            room: Condvar::new(),
+        }
+    }
+
+    fn push(&self, message: Message<E>) -> bool {
+        let mut queue = self.queue.lock().expect("lock");
+        if queue.disconnected {
+            return false;
+        }
+        queue.messages.push_back(message);
+        self.ready.notify_one();
+        true
+    }
+
+    fn send(&self, event: E, overflow: Overflow) -> Result<TrySend<E>, TrySendError<E>> {
+        let mut queue = self.queue.lock().expect("lock");
+        let mut evicted = None;
+
+        match self.cap {
+            Some(0) if overflow == Overflow::Block => {}
+            Some(cap) if queue.events >= cap => match overflow {
+                Overflow::Block => {
+                    queue = self
+                        .room
+                        .wait_while(queue, |queue| !queue.disconnected && queue.events >= cap)
+                        .expect("lock");
+                }
+                Overflow::DropNewest => return Ok(TrySend::Dropped(event)),
+                Overflow::Error => return Err(TrySendError::Full(event)),
+                Overflow::DropOldest => {
+                    let oldest = queue
+                        .messages
+                        .iter()
+                        .position(|next| matches!(next, Message::Event(_)));
+                    match oldest.and_then(|idx| queue.messages.remove(idx)) {
+                        Some(Message::Event(oldest)) => {
+                            queue.events -= 1;
+                            queue.taken += 1;
+                            evicted = Some(oldest);
+                        }
+                        // Nothing to evict, ie. a zero capacity queue
+                        _ => return Ok(TrySend::Dropped(event)),
+                    }
+                }
+            },
+            _ => {}
+        }
+
+        if queue.disconnected {
+            return Err(TrySendError::Disconnected(event));
+        }
+
+        queue.messages.push_back(Message::Event(event));
+        queue.events += 1;
+        queue.queued += 1;
+        self.ready.notify_one();
+
+        // Like a rendezvous channel, wait until the worker took the event
+        if self.cap == Some(0) {
+            let ticket = queue.queued;
+            drop(
+                self.room
+                    .wait_while(queue, |queue| !queue.disconnected && queue.taken < ticket)
+                    .expect("lock"),
+            );
+        }
+
+        Ok(match evicted {
+            Some(evicted) => TrySend::Evicted(evicted),
+            None => TrySend::Sent,
+        })
+    }
+
+    /// Returns `None` once the channel is disconnected and empty.
+    fn recv(&self) -> Option<Message<E>> {
+        let mut queue = self
+            .ready
+            .wait_while(self.queue.lock().expect("lock"), |queue| {
+                !queue.disconnected && queue.messages.is_empty()
+            })
+            .expect("lock");
+
+        let next = queue.messages.pop_front()?;
+        if let Message::Event(_) = next {
+            queue.events -= 1;
+            queue.taken += 1;
+            self.room.notify_all();
+        }
+        Some(next)
+    }
+
+    // Messages queued so far can still be received
+    fn disconnect(&self) {
+        self.queue.lock().expect("lock").disconnected = true;
+        self.ready.notify_all();
+        self.room.notify_all();
+    }
 }
 
 pub struct Thread<E: 'static> {
-    #[allow(unused)]
-    handle: JoinHandle<()>,
-    channel: Sender<Message<E>>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+    done: Receiver<()>,
+    channel: Arc<Channel<E>>,
+    // Shared with the worker, which registers the listeners in order with the events
+    trigger: SyncDirect<E>,
+    pending: Pending,
+    closed: AtomicBool,
+    overflow: Overflow,
 }
 
 impl<E> Default for Thread<E>
@@ -33,25 +197,111 @@ where
     E: 'static + Send,
 {
     pub fn new() -> Thread<E> {
-        let (channel, rx) = unbounded();
+        Thread::spawn(None, Overflow::Block)
+    }
 
+    /// Create a thread transport which queues at most `cap` events.
+    pub fn bounded(cap: usize, overflow: Overflow) -> Thread<E> {
+        Thread::spawn(Some(cap), overflow)
+    }
+
+    fn spawn(cap: Option<usize>, overflow: Overflow) -> Thread<E> {
+        let channel = Arc::new(Channel::new(cap));
+        let (done_sx, done) = bounded(1);
+        let pending = Pending::default();
+
+        let trigger = SyncDirect::<E>::default();
+        let worker = trigger.clone();
+        let worker_channel = channel.clone();
+        let worker_pending = pending.clone();
         let handle = std::thread::spawn(move || {
-            let trigger = SyncDirect::<E>::default();
+            let trigger = worker;
+            let channel = worker_channel;
 
-            while let Ok(next) = rx.recv() {
-                match next {
-                    Message::Event(event) => {
-                        trigger.trigger(event);
-                    }
-                    Message::Listener(listener, subs) => {
-                        let subscription = trigger.create_listener(listener);
-                        subs.replace_inner(subscription);
-                    }
+            // Returns false when the worker should shut down
+            let handle = |next: Message<E>| match next {
+                Message::Event(event) => {
+                    // Unhandled listener panics are re-raised, which mustn't stop the worker
+                    catch_unwind(AssertUnwindSafe(|| trigger.trigger(event))).ok();
+                    worker_pending.dec();
+                    true
+                }
+                Message::Listener(listener, priority, subs) => {
+                    let subscription = trigger.create_listener_with_priority(listener, priority);
+                    subs.replace_inner(subscription);
+                    true
+                }
+                Message::PanicHandler(handler) => {
+                    trigger.set_panic_handler(handler);
+                    true
+                }
+                Message::Shutdown => false,
+            };
+
+            while let Some(next) = channel.recv() {
+                if !handle(next) {
+                    break;
                 }
             }
+
+            // Dispatch what was queued before the channel was disconnected
+            channel.disconnect();
+            while let Some(next) = channel.recv() {
+                handle(next);
+            }
+            done_sx.send(()).ok();
         });
 
-        Thread { handle, channel }
+        Thread {
+            handle: Mutex::new(Some(handle)),
+            done,
+            channel,
+            trigger,
+            pending,
+            closed: AtomicBool::new(false),
+            overflow,
+        }
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+}
+
+impl<E> Thread<E> {
+    fn send(&self, event: E) -> Result<TrySend<E>, TrySendError<E>> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(TrySendError::Disconnected(event));
+        }
+
+        self.pending.inc();
+        let ret = self.channel.send(event, self.overflow);
+        match &ret {
+            Ok(TrySend::Sent) => {}
+            // The evicted event won't be dispatched, the new one takes its place
+            Ok(TrySend::Evicted(_)) | Ok(TrySend::Dropped(_)) | Err(_) => self.pending.dec(),
+        }
+        ret
+    }
+
+    fn close(&self) {
+        if !self.closed.swap(true, Ordering::AcqRel) {
+            self.channel.push(Message::Shutdown);
+        }
+    }
+
+    fn join(&self) {
+        let handle = self.handle.lock().expect("lock").take();
+        if let Some(handle) = handle {
+            handle.join().ok();
+        }
+    }
+}
+
+impl<E> Drop for Thread<E> {
+    // The worker dispatches the queued events, then stops
+    fn drop(&mut self) {
+        self.channel.disconnect();
     }
 }
 
@@ -69,6 +319,11 @@ impl<E> Subscription for ThreadSubscription<E> {
             inner.detach();
         }
     }
+
+    fn is_closed(&self) -> bool {
+        // Not closed while the worker has yet to register the listener
+        self.0.read(|inner| inner.is_closed()).unwrap_or(false)
+    }
 }
 
 impl<E> Transport<'static, E> for Thread<E> {
@@ -77,22 +332,77 @@ impl<E> Transport<'static, E> for Thread<E> {
     type Subscription = ThreadSubscription<E>;
 
     fn create_listener<L>(&self, listener: L) -> Self::Subscription
+    where
+        L: crate::IntoListener<'static, Self::Listener, E>,
+    {
+        self.create_listener_with_priority(listener, 0)
+    }
+
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
     where
         L: crate::IntoListener<'static, Self::Listener, E>,
     {
         let subscription = ThreadSubscription(MutexState::default());
 
-        self.channel
-            .send(Message::Listener(
-                listener.into_listener(),
-                subscription.0.downgrade(),
-            ))
-            .ok();
+        self.channel.push(Message::Listener(
+            listener.into_listener(),
+            priority,
+            subscription.0.downgrade(),
+        ));
 
         subscription
     }
 
     fn trigger(&self, event: E) {
-        self.channel.send(Message::Event(event)).ok();
+        self.send(event).ok();
+    }
+
+    fn try_trigger(&self, event: E) -> Result<TrySend<E>, TrySendError<E>> {
+        self.send(event)
+    }
+
+    fn set_panic_handler(&self, handler: PanicHandler) {
+        self.channel.push(Message::PanicHandler(handler));
+    }
+
+    fn listener_count(&self) -> usize {
+        self.trigger.listener_count()
+    }
+
+    /// Includes the events waiting in the queue.
+    fn pending(&self) -> usize {
+        self.pending.count()
+    }
+
+    fn queue_capacity(&self) -> Option<usize> {
+        self.channel.cap
+    }
+
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.trigger.listener_stats()
+    }
+
+    fn flush(&self) {
+        self.pending.wait();
+    }
+
+    fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.pending.wait_timeout(timeout)
+    }
+
+    fn shutdown(&self) {
+        self.close();
+        self.join();
+    }
+
+    fn shutdown_timeout(&self, timeout: Duration) -> bool {
+        self.close();
+        match self.done.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => false,
+            _ => {
+                self.join();
+                true
+            }
+        }
     }
 }
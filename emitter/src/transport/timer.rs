@@ -0,0 +1,229 @@
+use core::time::Duration;
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+/// A call scheduled on a [`Timer`].
+pub trait Job {
+    fn run(self);
+}
+
+/// Runs [`Job`]s once their delay has passed, eg. the trailing calls of a
+/// [`Debounce`](super::Debounce).
+///
+/// Timers decide which jobs they accept, so a timer running jobs on another
+/// thread only takes `Send` jobs, while [`ManualTimer`] takes any.
+pub trait Timer<J: Job> {
+    fn schedule(&self, delay: Duration, job: J);
+}
+
+impl<J: Job, X: Timer<J>> Timer<J> for &X {
+    fn schedule(&self, delay: Duration, job: J) {
+        (**self).schedule(delay, job)
+    }
+}
+
+type LocalJob<'a> = Box<dyn FnOnce() + 'a>;
+
+struct ManualQueue<'a> {
+    now: Duration,
+    next: u64,
+    jobs: BTreeMap<(Duration, u64), LocalJob<'a>>,
+}
+
+/// A timer for tests and frame loops, which runs the jobs on the calling thread
+/// as time is moved forward with [`advance`](ManualTimer::advance).
+///
+/// It doesn't read a clock, so it works on every target.
+pub struct ManualTimer<'a> {
+    queue: Rc<RefCell<ManualQueue<'a>>>,
+}
+
+impl<'a> Clone for ManualTimer<'a> {
+    fn clone(&self) -> Self {
+        ManualTimer {
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+impl<'a> Default for ManualTimer<'a> {
+    fn default() -> Self {
+        ManualTimer {
+            queue: Rc::new(RefCell::new(ManualQueue {
+                now: Duration::ZERO,
+                next: 0,
+                jobs: BTreeMap::new(),
+            })),
+        }
+    }
+}
+
+impl<'a> ManualTimer<'a> {
+    pub fn new() -> ManualTimer<'a> {
+        ManualTimer::default()
+    }
+
+    /// How far time has been advanced.
+    pub fn now(&self) -> Duration {
+        self.queue.borrow().now
+    }
+
+    /// The number of scheduled jobs.
+    pub fn pending(&self) -> usize {
+        self.queue.borrow().jobs.len()
+    }
+
+    /// Move time forward by `by`, running the jobs which are due in the order of their
+    /// deadlines, including the ones scheduled meanwhile. Returns the number of jobs run.
+    pub fn advance(&self, by: Duration) -> usize {
+        let until = self.now() + by;
+        let mut count = 0;
+        loop {
+            // Taken out first, so jobs are free to schedule new ones
+            let job = {
+                let mut queue = self.queue.borrow_mut();
+                match queue.jobs.first_key_value() {
+                    Some((&(deadline, _), _)) if deadline <= until => {
+                        queue.now = deadline;
+                        queue.jobs.pop_first().map(|(_, job)| job)
+                    }
+                    _ => None,
+                }
+            };
+            match job {
+                Some(job) => {
+                    job();
+                    count += 1;
+                }
+                None => break,
+            }
+        }
+        self.queue.borrow_mut().now = until;
+        count
+    }
+}
+
+impl<'a, J> Timer<J> for ManualTimer<'a>
+where
+    J: Job + 'a,
+{
+    fn schedule(&self, delay: Duration, job: J) {
+        let mut queue = self.queue.borrow_mut();
+        let key = (queue.now + delay, queue.next);
+        queue.next += 1;
+        queue.jobs.insert(key, Box::new(move || job.run()));
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use self::thread::ThreadTimer;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod thread {
+    use core::time::Duration;
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Condvar, Mutex},
+        time::Instant,
+    };
+
+    use super::{Job, Timer};
+
+    type SendJob = Box<dyn FnOnce() + Send>;
+
+    struct Queue {
+        next: u64,
+        jobs: BTreeMap<(Instant, u64), SendJob>,
+        dropped: bool,
+    }
+
+    struct Shared {
+        queue: Mutex<Queue>,
+        cvar: Condvar,
+    }
+
+    // Stops the thread once the last clone is gone, after the remaining jobs ran
+    struct Handle(Arc<Shared>);
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            if let Ok(mut queue) = self.0.queue.lock() {
+                queue.dropped = true;
+            }
+            self.0.cvar.notify_one();
+        }
+    }
+
+    /// A timer running the jobs on one background thread, shared by its clones.
+    #[derive(Clone)]
+    pub struct ThreadTimer {
+        handle: Arc<Handle>,
+    }
+
+    impl Default for ThreadTimer {
+        fn default() -> Self {
+            ThreadTimer::new()
+        }
+    }
+
+    impl ThreadTimer {
+        pub fn new() -> ThreadTimer {
+            let shared = Arc::new(Shared {
+                queue: Mutex::new(Queue {
+                    next: 0,
+                    jobs: BTreeMap::new(),
+                    dropped: false,
+                }),
+                cvar: Condvar::new(),
+            });
+
+            let worker = shared.clone();
+            std::thread::spawn(move || {
+                let mut queue = worker.queue.lock().expect("lock");
+                loop {
+                    let deadline = match queue.jobs.first_key_value() {
+                        Some((&(deadline, _), _)) => deadline,
+                        None if queue.dropped => return,
+                        None => {
+                            queue = worker.cvar.wait(queue).expect("lock");
+                            continue;
+                        }
+                    };
+
+                    let now = Instant::now();
+                    if now < deadline {
+                        queue = worker
+                            .cvar
+                            .wait_timeout(queue, deadline - now)
+                            .expect("lock")
+                            .0;
+                        continue;
+                    }
+
+                    let (_, job) = queue.jobs.pop_first().expect("job");
+                    drop(queue);
+                    // A panicking job mustn't stop the others
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(job)).ok();
+                    queue = worker.queue.lock().expect("lock");
+                }
+            });
+
+            ThreadTimer {
+                handle: Arc::new(Handle(shared)),
+            }
+        }
+    }
+
+    impl<J> Timer<J> for ThreadTimer
+    where
+        J: Job + Send + 'static,
+    {
+        fn schedule(&self, delay: Duration, job: J) {
+            let shared = &self.handle.0;
+            let mut queue = shared.queue.lock().expect("lock");
+            let key = (Instant::now() + delay, queue.next);
+            queue.next += 1;
+            queue.jobs.insert(key, Box::new(move || job.run()));
+            shared.cvar.notify_one();
+        }
+    }
+}
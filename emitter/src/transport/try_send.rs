@@ -0,0 +1,47 @@
+use core::fmt;
+
+/// Outcome of a successful [`Transport::try_trigger`](crate::Transport::try_trigger).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum TrySend<E> {
+    /// The event was handed to the transport.
+    Sent,
+    /// The transport was full and the event was dropped.
+    Dropped(E),
+    /// The event was queued, but the oldest pending event was evicted to make room.
+    Evicted(E),
+}
+
+impl<E> TrySend<E> {
+    pub fn is_sent(&self) -> bool {
+        matches!(self, TrySend::Sent)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrySendError<E> {
+    /// The transport was full.
+    Full(E),
+    /// The transport is no longer running.
+    Disconnected(E),
+}
+
+impl<E> TrySendError<E> {
+    pub fn into_inner(self) -> E {
+        match self {
+            TrySendError::Full(e) => e,
+            TrySendError::Disconnected(e) => e,
+        }
+    }
+}
+
+impl<E> fmt::Display for TrySendError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "sending on a full transport"),
+            TrySendError::Disconnected(_) => write!(f, "sending on a disconnected transport"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for TrySendError<E> {}
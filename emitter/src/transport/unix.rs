@@ -0,0 +1,395 @@
+use std::{
+    io::{self, Read, Write},
+    net::Shutdown,
+    os::unix::net::{UnixListener, UnixStream},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use state::{MutexState, StateTrait};
+
+use crate::{
+    direct::sync::{SyncDirectSubscription, SyncListener},
+    Emitter, IntoListener, ListenerStats, PanicHandler, Subscription, SyncDirect, Transport,
+};
+
+/// The default [`Codec::max_frame_len`], 16 MiB.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// The default [`Unix::with_write_timeout`], 5 seconds.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serializes the events sent over a [`Unix`] socket.
+pub trait Codec {
+    fn encode<E: Serialize>(&self, event: &E) -> io::Result<Vec<u8>>;
+
+    fn decode<E: DeserializeOwned>(&self, bytes: &[u8]) -> io::Result<E>;
+
+    /// Larger events aren't sent, and connections receiving a larger frame are closed.
+    fn max_frame_len(&self) -> usize {
+        MAX_FRAME_LEN
+    }
+}
+
+/// A codec with a different [`Codec::max_frame_len`].
+#[derive(Debug, Clone, Copy)]
+pub struct Limited<C> {
+    codec: C,
+    max_frame_len: usize,
+}
+
+impl<C> Limited<C> {
+    pub fn new(codec: C, max_frame_len: usize) -> Limited<C> {
+        Limited {
+            codec,
+            max_frame_len,
+        }
+    }
+}
+
+impl<C: Codec> Codec for Limited<C> {
+    fn encode<E: Serialize>(&self, event: &E) -> io::Result<Vec<u8>> {
+        self.codec.encode(event)
+    }
+
+    fn decode<E: DeserializeOwned>(&self, bytes: &[u8]) -> io::Result<E> {
+        self.codec.decode(bytes)
+    }
+
+    fn max_frame_len(&self) -> usize {
+        self.max_frame_len
+    }
+}
+
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Codec for Bincode {
+    fn encode<E: Serialize>(&self, event: &E) -> io::Result<Vec<u8>> {
+        bincode::serialize(event).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn decode<E: DeserializeOwned>(&self, bytes: &[u8]) -> io::Result<E> {
+        bincode::deserialize(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl Codec for Json {
+    fn encode<E: Serialize>(&self, event: &E) -> io::Result<Vec<u8>> {
+        serde_json::to_vec(event).map_err(io::Error::from)
+    }
+
+    fn decode<E: DeserializeOwned>(&self, bytes: &[u8]) -> io::Result<E> {
+        serde_json::from_slice(bytes).map_err(io::Error::from)
+    }
+}
+
+// Frames are a big endian u32 length followed by the encoded event
+fn write_frame(stream: &mut UnixStream, frame: &[u8]) -> io::Result<()> {
+    stream.write_all(frame)?;
+    stream.flush()
+}
+
+// The length comes from the peer, so it is checked before allocating the frame
+fn read_frame(stream: &mut UnixStream, max_len: usize) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds the limit of {}", len, max_len),
+        ));
+    }
+    let mut frame = vec![0; len];
+    stream.read_exact(&mut frame)?;
+    Ok(frame)
+}
+
+struct Server {
+    path: PathBuf,
+    clients: MutexState<Vec<UnixStream>>,
+    closed: AtomicBool,
+    accept: Mutex<Option<JoinHandle<()>>>,
+    write_timeout: Mutex<Option<Duration>>,
+}
+
+impl Server {
+    fn close(&self) {
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        // Wake up the accept loop so it sees the flag
+        UnixStream::connect(&self.path).ok();
+        if let Some(handle) = self.accept.lock().expect("lock").take() {
+            handle.join().ok();
+        }
+
+        self.clients
+            .write(|clients| {
+                for client in clients.drain(..) {
+                    client.shutdown(Shutdown::Both).ok();
+                }
+            })
+            .ok();
+
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// A transport which dispatches events to local listeners, like [`SyncDirect`],
+/// and sends them to every process connected to its Unix domain socket.
+///
+/// Writes to the socket block, so a client not keeping up slows down emitting.
+/// Clients are dropped once writing to them fails or takes longer than the write timeout.
+pub struct Unix<E: 'static, C> {
+    local: SyncDirect<E>,
+    server: Arc<Server>,
+    codec: C,
+}
+
+impl<E, C: Clone> Clone for Unix<E, C> {
+    fn clone(&self) -> Self {
+        Unix {
+            local: self.local.clone(),
+            server: self.server.clone(),
+            codec: self.codec.clone(),
+        }
+    }
+}
+
+impl<E, C> Unix<E, C> {
+    /// Listen for clients at `path`. Removes the socket file when the last clone is dropped.
+    pub fn bind(path: impl AsRef<Path>, codec: C) -> io::Result<Unix<E, C>> {
+        let path = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+
+        let server = Arc::new(Server {
+            path,
+            clients: MutexState::new(Vec::new()),
+            closed: AtomicBool::new(false),
+            accept: Mutex::new(None),
+            write_timeout: Mutex::new(Some(WRITE_TIMEOUT)),
+        });
+
+        // The accept loop only holds on to what it needs, so dropping the transport closes it
+        let clients = server.clients.clone();
+        let handle = std::thread::spawn({
+            let server = Arc::downgrade(&server);
+            move || {
+                for stream in listener.incoming() {
+                    let timeout = match server.upgrade() {
+                        Some(server) if !server.closed.load(Ordering::Acquire) => {
+                            *server.write_timeout.lock().expect("lock")
+                        }
+                        _ => break,
+                    };
+                    // A client which stopped reading mustn't block emitting forever
+                    if let Ok(stream) = stream {
+                        if stream.set_write_timeout(timeout).is_ok() {
+                            clients.write(|clients| clients.push(stream)).ok();
+                        }
+                    }
+                }
+            }
+        });
+        *server.accept.lock().expect("lock") = Some(handle);
+
+        Ok(Unix {
+            local: SyncDirect::default(),
+            server,
+            codec,
+        })
+    }
+
+    /// How long writing an event to a client may block before the client is dropped,
+    /// [`WRITE_TIMEOUT`] by default. `None` waits for slow clients however long it takes.
+    /// Like [`UnixStream::set_write_timeout`], a zero timeout is invalid.
+    pub fn with_write_timeout(self, timeout: Option<Duration>) -> Self {
+        *self.server.write_timeout.lock().expect("lock") = timeout;
+        self.server
+            .clients
+            .write(|clients| clients.retain(|client| client.set_write_timeout(timeout).is_ok()))
+            .ok();
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.server.path
+    }
+
+    /// The number of connected clients.
+    pub fn clients(&self) -> usize {
+        self.server
+            .clients
+            .read(|clients| clients.len())
+            .unwrap_or(0)
+    }
+}
+
+impl<E, C> Unix<E, C>
+where
+    E: Serialize,
+    C: Codec,
+{
+    fn broadcast(&self, event: &E) {
+        // Events which can't be encoded or framed are only dispatched locally
+        let payload = match self.codec.encode(event) {
+            Ok(payload) if payload.len() <= self.codec.max_frame_len() => payload,
+            _ => return,
+        };
+        let len = match u32::try_from(payload.len()) {
+            Ok(len) => len,
+            Err(_) => return,
+        };
+
+        let mut frame = Vec::with_capacity(payload.len() + 4);
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(&payload);
+
+        self.server
+            .clients
+            .write(|clients| clients.retain_mut(|client| write_frame(client, &frame).is_ok()))
+            .ok();
+    }
+}
+
+impl<E, C> Transport<'static, E> for Unix<E, C>
+where
+    E: Serialize,
+    C: Codec,
+{
+    type Listener = SyncListener<E>;
+
+    type Subscription = SyncDirectSubscription<E>;
+
+    fn create_listener<L>(&self, listener: L) -> Self::Subscription
+    where
+        L: IntoListener<'static, Self::Listener, E>,
+    {
+        self.local.create_listener(listener)
+    }
+
+    fn create_listener_with_priority<L>(&self, listener: L, priority: i32) -> Self::Subscription
+    where
+        L: IntoListener<'static, Self::Listener, E>,
+    {
+        self.local.create_listener_with_priority(listener, priority)
+    }
+
+    fn trigger(&self, event: E) {
+        if !self.server.closed.load(Ordering::Acquire) {
+            self.broadcast(&event);
+        }
+        self.local.trigger(event);
+    }
+
+    fn listener_count(&self) -> usize {
+        self.local.listener_count()
+    }
+
+    fn pending(&self) -> usize {
+        self.local.pending()
+    }
+
+    fn listener_stats(&self) -> Vec<ListenerStats> {
+        self.local.listener_stats()
+    }
+
+    fn set_panic_handler(&self, handler: PanicHandler) {
+        self.local.set_panic_handler(handler)
+    }
+
+    /// Disconnect the clients and stop accepting new ones.
+    fn shutdown(&self) {
+        self.server.close();
+    }
+}
+
+/// A connection re-emitting the events of a [`Unix`] transport on a local emitter.
+///
+/// Closing or dropping it disconnects, detaching keeps the connection
+/// alive until the remote side goes away.
+pub struct UnixSubscription {
+    stream: Option<UnixStream>,
+    done: Arc<AtomicBool>,
+}
+
+impl Subscription for UnixSubscription {
+    fn close(self) {
+        drop(self)
+    }
+
+    fn detach(mut self) {
+        self.stream = None;
+    }
+
+    fn is_closed(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for UnixSubscription {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            stream.shutdown(Shutdown::Both).ok();
+        }
+    }
+}
+
+impl<E, T> Emitter<E, T>
+where
+    E: DeserializeOwned + 'static,
+    T: Transport<'static, E> + Send + 'static,
+{
+    /// Connect to the [`Unix`] transport at `path` and emit its events on this emitter.
+    pub fn connect_unix<C>(&self, path: impl AsRef<Path>, codec: C) -> io::Result<UnixSubscription>
+    where
+        Self: Clone,
+        C: Codec + Send + 'static,
+    {
+        let stream = UnixStream::connect(path)?;
+        let mut reader = stream.try_clone()?;
+        let done = Arc::new(AtomicBool::new(false));
+
+        let emitter = self.clone();
+        let finished = done.clone();
+        std::thread::spawn(move || {
+            // A frame over the limit ends the connection, as the stream can't be trusted anymore
+            while let Ok(frame) = read_frame(&mut reader, codec.max_frame_len()) {
+                // Skip events this side doesn't understand
+                if let Ok(event) = codec.decode(&frame) {
+                    // Unhandled listener panics are re-raised, which mustn't end the connection
+                    catch_unwind(AssertUnwindSafe(|| emitter.emit(event))).ok();
+                }
+            }
+            finished.store(true, Ordering::Release);
+        });
+
+        Ok(UnixSubscription {
+            stream: Some(stream),
+            done,
+        })
+    }
+}
@@ -1,7 +1,7 @@
 // use crate::Downgrade;
 
 use super::async_locking::AsyncLockApi;
-use super::lock::{Downgrade, WeakLock};
+use super::lock::{Address, Downgrade, WeakLock};
 use async_trait::async_trait;
 
 #[cfg(not(feature = "std"))]
@@ -55,6 +55,12 @@ where
 
 impl<'a, L, T: 'a> AsyncLock<'a, T> for AsyncSendLock<L> where L: AsyncLockApi<'a, T> + Send + Sync {}
 
+impl<L> Address for AsyncSendLock<L> {
+    fn address(&self) -> usize {
+        Arc::as_ptr(&self.lock) as *const () as usize
+    }
+}
+
 impl<L> Downgrade for AsyncSendLock<L> {
     type Weak = WeakAsyncSendLock<L>;
 
@@ -9,7 +9,7 @@ use std::{
     sync::{Arc, Weak as ArcWeak},
 };
 
-use crate::LockApi;
+use crate::{LockApi, TryLockApi};
 
 pub trait Downgrade {
     type Weak: WeakLock<Output = Self>;
@@ -18,6 +18,11 @@ pub trait Downgrade {
 
 pub trait Lock<'a, T: 'a>: LockApi<'a, T> + Downgrade + Clone {}
 
+/// Identifies the lock shared by all clones of a handle.
+pub trait Address {
+    fn address(&self) -> usize;
+}
+
 pub trait WeakLock: Clone {
     type Output;
 
@@ -62,6 +67,25 @@ impl<L> Clone for SendLock<L> {
 
 impl<'a, L, T: 'a> Lock<'a, T> for SendLock<L> where L: LockApi<'a, T> {}
 
+impl<'a, L, T> TryLockApi<'a, T> for SendLock<L>
+where
+    L: TryLockApi<'a, T>,
+{
+    fn try_read(&'a self) -> Option<Self::ReadGuard> {
+        self.lock.try_read()
+    }
+
+    fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+        self.lock.try_write()
+    }
+}
+
+impl<L> Address for SendLock<L> {
+    fn address(&self) -> usize {
+        Arc::as_ptr(&self.lock) as *const () as usize
+    }
+}
+
 impl<L> Downgrade for SendLock<L> {
     type Weak = WeakSendLock<L>;
 
@@ -127,6 +151,25 @@ impl<L> Clone for NonSendLock<L> {
 
 impl<'a, L, T: 'a> Lock<'a, T> for NonSendLock<L> where L: LockApi<'a, T> {}
 
+impl<'a, L, T> TryLockApi<'a, T> for NonSendLock<L>
+where
+    L: TryLockApi<'a, T>,
+{
+    fn try_read(&'a self) -> Option<Self::ReadGuard> {
+        self.lock.try_read()
+    }
+
+    fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+        self.lock.try_write()
+    }
+}
+
+impl<L> Address for NonSendLock<L> {
+    fn address(&self) -> usize {
+        Rc::as_ptr(&self.lock) as *const () as usize
+    }
+}
+
 impl<L> Downgrade for NonSendLock<L> {
     type Weak = WeakNonSendLock<L>;
 
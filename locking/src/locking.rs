@@ -22,6 +22,13 @@ pub trait LockApi<'a, T> {
     fn new(inner: T) -> Self;
 }
 
+/// Locks which can be taken without blocking.
+pub trait TryLockApi<'a, T>: LockApi<'a, T> {
+    fn try_read(&'a self) -> Option<Self::ReadGuard>;
+
+    fn try_write(&'a self) -> Option<Self::ReadWriteGuard>;
+}
+
 impl<'a, T> LockApiReadGuard<'a, T> for Ref<'a, T> {
     fn get(&self) -> &T {
         self.deref()
@@ -61,6 +68,19 @@ where
     }
 }
 
+impl<'a, T> TryLockApi<'a, T> for RefCell<T>
+where
+    T: 'a,
+{
+    fn try_read(&'a self) -> Option<Self::ReadGuard> {
+        self.try_borrow().ok()
+    }
+
+    fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+        self.try_borrow_mut().ok()
+    }
+}
+
 #[cfg(feature = "parking_lot")]
 mod parking_lot_impl {
     // Mutex
@@ -100,6 +120,19 @@ mod parking_lot_impl {
         }
     }
 
+    impl<'a, T> TryLockApi<'a, T> for Mutex<T>
+    where
+        T: 'a,
+    {
+        fn try_read(&'a self) -> Option<Self::ReadGuard> {
+            self.try_lock()
+        }
+
+        fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+            self.try_lock()
+        }
+    }
+
     // RwLock
 
     impl<'a, T> LockApiReadGuard<'a, T> for RwLockReadGuard<'a, T> {
@@ -140,6 +173,19 @@ mod parking_lot_impl {
             RwLock::new(inner)
         }
     }
+
+    impl<'a, T> TryLockApi<'a, T> for RwLock<T>
+    where
+        T: 'a,
+    {
+        fn try_read(&'a self) -> Option<Self::ReadGuard> {
+            (*self).try_read()
+        }
+
+        fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+            (*self).try_write()
+        }
+    }
 }
 
 #[cfg(feature = "spin")]
@@ -181,6 +227,19 @@ mod spin_impl {
         }
     }
 
+    impl<'a, T> TryLockApi<'a, T> for Mutex<T>
+    where
+        T: 'a,
+    {
+        fn try_read(&'a self) -> Option<Self::ReadGuard> {
+            self.try_lock()
+        }
+
+        fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+            self.try_lock()
+        }
+    }
+
     // RwLock
 
     impl<'a, T> LockApiReadGuard<'a, T> for RwLockReadGuard<'a, T> {
@@ -221,13 +280,26 @@ mod spin_impl {
             RwLock::new(inner)
         }
     }
+
+    impl<'a, T> TryLockApi<'a, T> for RwLock<T>
+    where
+        T: 'a,
+    {
+        fn try_read(&'a self) -> Option<Self::ReadGuard> {
+            (*self).try_read()
+        }
+
+        fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+            (*self).try_write()
+        }
+    }
 }
 
 #[cfg(feature = "std")]
 mod std_impl {
     // Mutex
     use super::*;
-    use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+    use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
 
     impl<'a, T> LockApiReadGuard<'a, T> for MutexGuard<'a, T> {
         fn get(&self) -> &T {
@@ -262,6 +334,23 @@ mod std_impl {
         }
     }
 
+    impl<'a, T> TryLockApi<'a, T> for Mutex<T>
+    where
+        T: 'a,
+    {
+        fn try_read(&'a self) -> Option<Self::ReadGuard> {
+            self.try_write()
+        }
+
+        fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+            match self.try_lock() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::WouldBlock) => None,
+                Err(TryLockError::Poisoned(_)) => panic!("lock"),
+            }
+        }
+    }
+
     // RwLock
 
     impl<'a, T> LockApiReadGuard<'a, T> for RwLockReadGuard<'a, T> {
@@ -302,4 +391,25 @@ mod std_impl {
             RwLock::new(inner)
         }
     }
+
+    impl<'a, T> TryLockApi<'a, T> for RwLock<T>
+    where
+        T: 'a,
+    {
+        fn try_read(&'a self) -> Option<Self::ReadGuard> {
+            match (*self).try_read() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::WouldBlock) => None,
+                Err(TryLockError::Poisoned(_)) => panic!("read lock"),
+            }
+        }
+
+        fn try_write(&'a self) -> Option<Self::ReadWriteGuard> {
+            match (*self).try_write() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::WouldBlock) => None,
+                Err(TryLockError::Poisoned(_)) => panic!("write lock"),
+            }
+        }
+    }
 }
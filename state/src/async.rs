@@ -10,6 +10,9 @@ use std::future::Future;
 
 use crate::{Downgrade, State, StateError, StateTrait};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 #[async_trait]
 pub trait AsyncIntoInner<T> {
     async fn into_inner(self) -> Option<T>;
@@ -82,7 +85,7 @@ pub type WeakAsyncMutexState<T> = WeakAsyncLockState<T, WeakAsyncSendLock<Mutex<
 pub type AsyncRwLockState<T> = AsyncLockState<T, RwLock<Option<T>>>;
 
 pub struct AsyncLockState<T, L> {
-    lock: L,
+    pub(crate) lock: L,
     _t: PhantomData<T>,
 }
 
@@ -157,6 +160,60 @@ where
     }
 }
 
+// Serializing can't await the lock, so it waits for it on the current thread
+#[cfg(any(feature = "serde", test))]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    use std::{
+        sync::Arc,
+        task::{Context, Poll, Wake, Waker},
+        thread::{self, Thread},
+    };
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(ret) => return ret,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Waits for the lock, so don't serialize a state while holding its lock on the same thread.
+#[cfg(feature = "serde")]
+impl<T, L> Serialize for AsyncLockState<T, L>
+where
+    for<'a> L: AsyncLockApi<'a, Option<T>>,
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        block_on(self.lock.read()).get().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, L> Deserialize<'de> for AsyncLockState<T, L>
+where
+    for<'a> L: AsyncLockApi<'a, Option<T>>,
+    T: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(AsyncLockState {
+            lock: L::new(Option::deserialize(deserializer)?),
+            _t: PhantomData,
+        })
+    }
+}
+
 impl<T, L> Downgrade for AsyncLockState<T, L>
 where
     L: locking::lock::Downgrade,
@@ -0,0 +1,312 @@
+use core::marker::PhantomData;
+use std::{
+    rc::Rc,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{Observed, StateError, StateTrait};
+
+/// States which count their changes, so values computed from them can be cached.
+pub trait Version {
+    fn version(&self) -> u64;
+}
+
+impl<S, T> Version for Observed<S, T> {
+    fn version(&self) -> u64 {
+        Observed::version(self)
+    }
+}
+
+impl<X: Version> Version for &X {
+    fn version(&self) -> u64 {
+        (**self).version()
+    }
+}
+
+/// Computes the value of a [`Derived`] state from its sources.
+pub trait Compute<U> {
+    /// Changes whenever one of the sources changed.
+    fn version(&self) -> u64;
+
+    fn compute(&self) -> Result<U, StateError>;
+
+    fn is_valid(&self) -> bool;
+}
+
+/// The sources of a [`Derived`] state, with the projection computing its value.
+pub struct Selector<S, T, F> {
+    sources: S,
+    func: F,
+    _t: PhantomData<fn() -> T>,
+}
+
+impl<A, TA, F, U> Compute<U> for Selector<(A,), (TA,), F>
+where
+    A: StateTrait<TA> + Version,
+    F: Fn(&TA) -> U,
+{
+    fn version(&self) -> u64 {
+        self.sources.0.version()
+    }
+
+    fn compute(&self) -> Result<U, StateError> {
+        self.sources.0.read(|a| (self.func)(a))
+    }
+
+    fn is_valid(&self) -> bool {
+        self.sources.0.is_valid()
+    }
+}
+
+impl<A, B, TA, TB, F, U> Compute<U> for Selector<(A, B), (TA, TB), F>
+where
+    A: StateTrait<TA> + Version,
+    B: StateTrait<TB> + Version,
+    F: Fn(&TA, &TB) -> U,
+{
+    // Versions only grow, so their sum changes whenever one of them does
+    fn version(&self) -> u64 {
+        self.sources.0.version() + self.sources.1.version()
+    }
+
+    fn compute(&self) -> Result<U, StateError> {
+        let (a, b) = &self.sources;
+        a.read(|a| b.read(|b| (self.func)(a, b)))?
+    }
+
+    fn is_valid(&self) -> bool {
+        self.sources.0.is_valid() && self.sources.1.is_valid()
+    }
+}
+
+impl<A, B, C, TA, TB, TC, F, U> Compute<U> for Selector<(A, B, C), (TA, TB, TC), F>
+where
+    A: StateTrait<TA> + Version,
+    B: StateTrait<TB> + Version,
+    C: StateTrait<TC> + Version,
+    F: Fn(&TA, &TB, &TC) -> U,
+{
+    fn version(&self) -> u64 {
+        self.sources.0.version() + self.sources.1.version() + self.sources.2.version()
+    }
+
+    fn compute(&self) -> Result<U, StateError> {
+        let (a, b, c) = &self.sources;
+        a.read(|a| b.read(|b| c.read(|c| (self.func)(a, b, c)))?)?
+    }
+
+    fn is_valid(&self) -> bool {
+        self.sources.0.is_valid() && self.sources.1.is_valid() && self.sources.2.is_valid()
+    }
+}
+
+// The value is shared, so neither the projection nor the readers run with the cache locked
+struct Inner<U, C: ?Sized> {
+    cache: Mutex<Option<(u64, Arc<U>)>>,
+    selector: C,
+}
+
+impl<U, C> Inner<U, C>
+where
+    C: Compute<U> + ?Sized,
+{
+    fn new(selector: C) -> Inner<U, C>
+    where
+        C: Sized,
+    {
+        Inner {
+            cache: Mutex::new(None),
+            selector,
+        }
+    }
+
+    // Nothing panics while the cache is locked, but a poisoned cache is still usable
+    fn cache(&self) -> MutexGuard<'_, Option<(u64, Arc<U>)>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_stale(&self) -> bool {
+        let version = self.selector.version();
+        match &*self.cache() {
+            Some((cached, _)) => *cached != version,
+            None => true,
+        }
+    }
+
+    fn read<F, R>(&self, func: F) -> Result<R, StateError>
+    where
+        F: FnOnce(&U) -> R,
+    {
+        // Taken before computing, so a change during the computation is picked up by the next read
+        let version = self.selector.version();
+
+        let cached = match &*self.cache() {
+            Some((cached, value)) if *cached == version => Some(value.clone()),
+            _ => None,
+        };
+
+        let value = match cached {
+            Some(value) => value,
+            None => {
+                let value = Arc::new(self.selector.compute()?);
+                let mut cache = self.cache();
+                // Another reader may have cached a newer value meanwhile
+                if !matches!(&*cache, Some((cached, _)) if *cached > version) {
+                    *cache = Some((version, value.clone()));
+                }
+                value
+            }
+        };
+
+        Ok(func(&value))
+    }
+}
+
+/// A read-only state computed from one or more [`Observed`] states.
+///
+/// The value is computed on the first read and cached until one of the sources changes.
+/// Reading fails with the error of the first source which can't be read,
+/// and writing always fails with [`StateError::ReadOnly`].
+pub struct Derived<U> {
+    inner: Rc<Inner<U, dyn Compute<U>>>,
+}
+
+impl<U> Clone for Derived<U> {
+    fn clone(&self) -> Self {
+        Derived {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<U: 'static> Derived<U> {
+    /// Derive from a tuple of sources, eg. `Derived::new((a, b), |a, b| a + b)`.
+    pub fn new<S, T, F>(sources: S, func: F) -> Derived<U>
+    where
+        Selector<S, T, F>: Compute<U> + 'static,
+    {
+        Derived::with_selector(Selector {
+            sources,
+            func,
+            _t: PhantomData,
+        })
+    }
+
+    /// Derive from a single source.
+    pub fn map<S, T, F>(source: S, func: F) -> Derived<U>
+    where
+        Selector<(S,), (T,), F>: Compute<U> + 'static,
+    {
+        Derived::new((source,), func)
+    }
+
+    pub fn with_selector(selector: impl Compute<U> + 'static) -> Derived<U> {
+        Derived {
+            inner: Rc::new(Inner::new(selector)),
+        }
+    }
+}
+
+impl<U> Derived<U> {
+    /// Whether the cached value is out of date, or hasn't been computed yet.
+    pub fn is_stale(&self) -> bool {
+        self.inner.is_stale()
+    }
+}
+
+impl<U> Version for Derived<U> {
+    fn version(&self) -> u64 {
+        self.inner.selector.version()
+    }
+}
+
+impl<U> StateTrait<U> for Derived<U> {
+    fn read<F, R>(&self, func: F) -> Result<R, StateError>
+    where
+        F: FnOnce(&U) -> R,
+    {
+        self.inner.read(func)
+    }
+
+    fn write<F, R>(&self, _func: F) -> Result<R, StateError>
+    where
+        F: FnOnce(&mut U) -> R,
+    {
+        Err(StateError::ReadOnly)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.inner.selector.is_valid()
+    }
+}
+
+/// A [`Derived`] state which can be shared between threads, for `Send + Sync` sources.
+pub struct SyncDerived<U> {
+    inner: Arc<Inner<U, dyn Compute<U> + Send + Sync>>,
+}
+
+impl<U> Clone for SyncDerived<U> {
+    fn clone(&self) -> Self {
+        SyncDerived {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<U: 'static> SyncDerived<U> {
+    pub fn new<S, T, F>(sources: S, func: F) -> SyncDerived<U>
+    where
+        Selector<S, T, F>: Compute<U> + Send + Sync + 'static,
+    {
+        SyncDerived::with_selector(Selector {
+            sources,
+            func,
+            _t: PhantomData,
+        })
+    }
+
+    pub fn map<S, T, F>(source: S, func: F) -> SyncDerived<U>
+    where
+        Selector<(S,), (T,), F>: Compute<U> + Send + Sync + 'static,
+    {
+        SyncDerived::new((source,), func)
+    }
+
+    pub fn with_selector(selector: impl Compute<U> + Send + Sync + 'static) -> SyncDerived<U> {
+        SyncDerived {
+            inner: Arc::new(Inner::new(selector)),
+        }
+    }
+}
+
+impl<U> SyncDerived<U> {
+    pub fn is_stale(&self) -> bool {
+        self.inner.is_stale()
+    }
+}
+
+impl<U> Version for SyncDerived<U> {
+    fn version(&self) -> u64 {
+        self.inner.selector.version()
+    }
+}
+
+impl<U> StateTrait<U> for SyncDerived<U> {
+    fn read<F, R>(&self, func: F) -> Result<R, StateError>
+    where
+        F: FnOnce(&U) -> R,
+    {
+        self.inner.read(func)
+    }
+
+    fn write<F, R>(&self, _func: F) -> Result<R, StateError>
+    where
+        F: FnOnce(&mut U) -> R,
+    {
+        Err(StateError::ReadOnly)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.inner.selector.is_valid()
+    }
+}
@@ -4,11 +4,20 @@ use core::fmt;
 pub enum StateError {
     Upgrade,
     Empty,
+    ReadOnly,
+    Timeout,
+    /// The same state was passed to a transaction twice.
+    Duplicate,
 }
 
 impl fmt::Display for StateError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "invalid state")
+        match self {
+            StateError::ReadOnly => write!(f, "read only state"),
+            StateError::Timeout => write!(f, "timed out locking state"),
+            StateError::Duplicate => write!(f, "state used twice in a transaction"),
+            _ => write!(f, "invalid state"),
+        }
     }
 }
 
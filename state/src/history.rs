@@ -0,0 +1,223 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{IntoInner, StateError, StateTrait};
+
+/// A change which knows how to undo itself, for values too big to copy on every write.
+pub trait Patch<T>: Send {
+    fn apply(&self, value: &mut T);
+
+    fn revert(&self, value: &mut T);
+}
+
+enum Change<T> {
+    // The value on the other side of the change
+    Snapshot(T),
+    Patch(Box<dyn Patch<T>>),
+}
+
+impl<T> Change<T> {
+    // Swaps the change around, so it can go on the other stack
+    fn undo(self, value: &mut T) -> Change<T> {
+        match self {
+            Change::Snapshot(other) => Change::Snapshot(core::mem::replace(value, other)),
+            Change::Patch(patch) => {
+                patch.revert(value);
+                Change::Patch(patch)
+            }
+        }
+    }
+
+    fn redo(self, value: &mut T) -> Change<T> {
+        match self {
+            Change::Snapshot(other) => Change::Snapshot(core::mem::replace(value, other)),
+            Change::Patch(patch) => {
+                patch.apply(value);
+                Change::Patch(patch)
+            }
+        }
+    }
+}
+
+struct Log<T> {
+    undo: VecDeque<Change<T>>,
+    redo: Vec<Change<T>>,
+    depth: usize,
+}
+
+impl<T> Log<T> {
+    fn push(&mut self, change: Change<T>) {
+        self.redo.clear();
+        self.undo.push_back(change);
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+}
+
+/// A state wrapper recording every `write`, so it can be undone and redone.
+///
+/// Writes copy the old value, unless they go through [`apply`](History::apply) with a [`Patch`].
+/// Only the last `depth` changes are kept, and a new change drops the ones which were undone.
+pub struct History<S, T> {
+    inner: S,
+    log: Arc<Mutex<Log<T>>>,
+}
+
+impl<S: Clone, T> Clone for History<S, T> {
+    fn clone(&self) -> Self {
+        History {
+            inner: self.inner.clone(),
+            log: self.log.clone(),
+        }
+    }
+}
+
+impl<S, T> History<S, T> {
+    pub fn new(inner: S, depth: usize) -> History<S, T> {
+        History {
+            inner,
+            log: Arc::new(Mutex::new(Log {
+                undo: VecDeque::new(),
+                redo: Vec::new(),
+                depth,
+            })),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    // Changes are only recorded once a write returned, so a write
+    // panicking while the log is locked leaves it usable
+    fn log(&self) -> MutexGuard<'_, Log<T>> {
+        self.log.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.log().undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.log().redo.is_empty()
+    }
+
+    /// Forget every recorded change.
+    pub fn clear(&self) {
+        let mut log = self.log();
+        log.undo.clear();
+        log.redo.clear();
+    }
+}
+
+// The log stays locked while writing, so changes are recorded in the order they were made
+impl<S, T> History<S, T>
+where
+    S: StateTrait<T>,
+{
+    /// Apply `patch` and record it instead of a copy of the value.
+    pub fn apply<P>(&self, patch: P) -> Result<(), StateError>
+    where
+        P: Patch<T> + 'static,
+    {
+        let mut log = self.log();
+        self.inner.write(|value| patch.apply(value))?;
+        log.push(Change::Patch(Box::new(patch)));
+        Ok(())
+    }
+
+    /// Undo the last change. Returns false if there was nothing to undo.
+    pub fn undo(&self) -> Result<bool, StateError> {
+        let mut log = self.log();
+        let change = match log.undo.pop_back() {
+            Some(change) => change,
+            None => return Ok(false),
+        };
+
+        let mut change = Some(change);
+        match self
+            .inner
+            .write(|value| change.take().map(|next| next.undo(value)))
+        {
+            Ok(redo) => log.redo.extend(redo),
+            // The change wasn't used, so it can be undone later
+            Err(err) => {
+                log.undo.extend(change);
+                return Err(err);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Redo the last undone change. Returns false if there was nothing to redo.
+    pub fn redo(&self) -> Result<bool, StateError> {
+        let mut log = self.log();
+        let change = match log.redo.pop() {
+            Some(change) => change,
+            None => return Ok(false),
+        };
+
+        let mut change = Some(change);
+        match self
+            .inner
+            .write(|value| change.take().map(|next| next.redo(value)))
+        {
+            Ok(undo) => log.undo.extend(undo),
+            Err(err) => {
+                log.redo.extend(change);
+                return Err(err);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<S, T> StateTrait<T> for History<S, T>
+where
+    S: StateTrait<T>,
+    T: Clone,
+{
+    fn read<F, U>(&self, func: F) -> Result<U, StateError>
+    where
+        F: FnOnce(&T) -> U,
+    {
+        self.inner.read(func)
+    }
+
+    fn write<F, U>(&self, func: F) -> Result<U, StateError>
+    where
+        F: FnOnce(&mut T) -> U,
+    {
+        let mut log = self.log();
+        let (old, ret) = self.inner.write(|value| (value.clone(), func(value)))?;
+        log.push(Change::Snapshot(old));
+        Ok(ret)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+    }
+}
+
+impl<S, T> IntoInner<T> for History<S, T>
+where
+    S: StateTrait<T> + IntoInner<T>,
+    T: Clone,
+{
+    fn into_inner(self) -> Option<T> {
+        self.inner.into_inner()
+    }
+
+    /// Recorded like a write, if the state wasn't empty.
+    fn replace_inner(&self, other: T) -> Option<T> {
+        let mut log = self.log();
+        let old = self.inner.replace_inner(other);
+        if let Some(old) = &old {
+            log.push(Change::Snapshot(old.clone()));
+        }
+        old
+    }
+}
@@ -10,6 +10,13 @@ mod types;
 #[cfg(feature = "async")]
 mod r#async;
 
+#[cfg(feature = "std")]
+mod derived;
+#[cfg(feature = "std")]
+mod history;
+#[cfg(feature = "std")]
+mod observe;
+
 pub use self::state::*;
 
 pub use self::{error::*, types::*};
@@ -17,8 +24,498 @@ pub use self::{error::*, types::*};
 #[cfg(feature = "async")]
 pub use self::r#async::*;
 
+#[cfg(feature = "std")]
+pub use self::{derived::*, history::*, observe::*};
+
+#[cfg(feature = "serde")]
+mod persist;
+
+#[cfg(feature = "serde")]
+pub use self::persist::*;
+
 #[cfg(feature = "sync")]
 pub mod sync;
 
 #[cfg(feature = "sync")]
 pub use sync::*;
+
+#[cfg(any(feature = "sync", feature = "async"))]
+mod transact;
+
+#[cfg(any(feature = "sync", feature = "async"))]
+pub use self::transact::*;
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_observed() {
+        use std::sync::{Arc, Mutex};
+
+        let state = Observed::new(State::new(1));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let diffs = Arc::new(Mutex::new(Vec::new()));
+
+        let log = seen.clone();
+        let id = state.subscribe(move |value: &i32| log.lock().unwrap().push(*value));
+        let log = diffs.clone();
+        state.subscribe_diff(move |old: &i32, new: &i32| log.lock().unwrap().push((*old, *new)));
+
+        state.write(|value| *value += 1).unwrap();
+        assert_eq!(state.replace_inner(10), Some(2));
+        assert!(state.unsubscribe(id));
+        state.write(|value| *value *= 2).unwrap();
+
+        assert_eq!(state.version(), 3);
+        assert_eq!(*seen.lock().unwrap(), vec![2, 10]);
+        assert_eq!(*diffs.lock().unwrap(), vec![(1, 2), (2, 10), (10, 20)]);
+    }
+
+    #[test]
+    fn test_observed_watch() {
+        use std::{
+            future::Future,
+            pin::pin,
+            sync::{
+                atomic::{AtomicBool, Ordering},
+                Arc,
+            },
+            task::{Context, Poll, Wake, Waker},
+        };
+
+        struct Flag(AtomicBool);
+
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::Release);
+            }
+        }
+
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let state = Observed::new(State::new(0));
+        let mut watcher = state.watch();
+        {
+            let mut changed = pin!(watcher.changed());
+            assert_eq!(changed.as_mut().poll(&mut cx), Poll::Pending);
+
+            state.write(|value| *value = 1).unwrap();
+            assert!(flag.0.load(Ordering::Acquire));
+            assert_eq!(changed.as_mut().poll(&mut cx), Poll::Ready(()));
+        }
+        assert!(!watcher.has_changed());
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_observed_concurrent_diff() {
+        use std::sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let state = Observed::new(MutexState::new(0u64));
+        let skipped = Arc::new(AtomicUsize::new(0));
+
+        let count = skipped.clone();
+        state.subscribe_diff(move |old: &u64, new: &u64| {
+            if *new != *old + 1 {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        // Observers are called one change at a time, in the order of the writes
+        let last = AtomicU64::new(0);
+        let count = skipped.clone();
+        state.subscribe(move |new: &u64| {
+            if last.swap(*new, Ordering::Relaxed) + 1 != *new {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..5000 {
+                        state.write(|value| *value += 1).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(state.read(|value| *value).unwrap(), 20000);
+        assert_eq!(skipped.load(Ordering::Relaxed), 0);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_observed_reentrant() {
+        use std::sync::{Arc, Mutex};
+
+        let state = Observed::new(MutexState::new(0));
+        let double = SyncDerived::map(state.clone(), |value: &i32| value * 2);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        // The state is unlocked while observers run, so they can read and write it
+        let (inner, log) = (state.clone(), seen.clone());
+        state.subscribe(move |new: &i32| {
+            let value = inner.read(|value| *value).unwrap();
+            let derived = double.read(|value| *value).unwrap();
+            log.lock().unwrap().push((*new, value, derived));
+            if *new < 3 {
+                inner.write(|value| *value += 1).unwrap();
+            }
+        });
+
+        state.write(|value| *value = 1).unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![(1, 1, 2), (2, 2, 4), (3, 3, 6)]);
+
+        assert_eq!(state.replace_inner(10), Some(3));
+        assert_eq!(seen.lock().unwrap().last(), Some(&(10, 10, 20)));
+    }
+
+    #[test]
+    fn test_derived() {
+        use std::{cell::Cell, rc::Rc};
+
+        let a = Observed::new(State::new(2));
+        let b = Observed::new(State::new(3));
+        let computed = Rc::new(Cell::new(0));
+
+        let count = computed.clone();
+        let sum = Derived::new((a.clone(), b.clone()), move |a: &i32, b: &i32| {
+            count.set(count.get() + 1);
+            a + b
+        });
+
+        assert!(sum.is_stale());
+        assert_eq!(sum.read(|value| *value).unwrap(), 5);
+        assert_eq!(sum.read(|value| *value).unwrap(), 5);
+        assert_eq!(computed.get(), 1);
+
+        a.write(|value| *value = 10).unwrap();
+        assert!(sum.is_stale());
+        assert_eq!(sum.read(|value| *value).unwrap(), 13);
+        assert_eq!(computed.get(), 2);
+
+        // The cache isn't locked while reading, so the same state can be read again
+        let twice = sum.read(|value| value + sum.read(|value| *value).unwrap());
+        assert_eq!(twice.unwrap(), 26);
+
+        assert!(matches!(
+            sum.write(|value| *value = 0),
+            Err(StateError::ReadOnly)
+        ));
+    }
+
+    #[test]
+    fn test_derived_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let source = Observed::new(State::new(0));
+        let half = Derived::map(source.clone(), |value: &i32| {
+            assert!(*value >= 0);
+            value / 2
+        });
+
+        source.write(|value| *value = -1).unwrap();
+        assert!(catch_unwind(AssertUnwindSafe(|| half.read(|value| *value))).is_err());
+
+        source.write(|value| *value = 4).unwrap();
+        assert_eq!(half.read(|value| *value).unwrap(), 2);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_sync_derived() {
+        let source = Observed::new(MutexState::new(1));
+        let double = SyncDerived::map(source.clone(), |value: &i32| value * 2);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| source.write(|value| *value = 21).unwrap());
+        });
+
+        let double = std::thread::spawn(move || double.read(|value| *value).unwrap());
+        assert_eq!(double.join().unwrap(), 42);
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn test_transaction() {
+        use std::time::Duration;
+
+        let a = MutexState::new(100);
+        let b = MutexState::new(0);
+
+        // Opposite orders would deadlock if the locks were taken in argument order
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..10000 {
+                    transaction((&a, &b), |(a, b)| {
+                        *a -= 1;
+                        *b += 1;
+                    })
+                    .unwrap();
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..10000 {
+                    transaction((&b, &a), |(b, a)| {
+                        *b -= 1;
+                        *a += 1;
+                    })
+                    .unwrap();
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..1000 {
+                    try_transaction((&b, &a), Duration::from_secs(5), |(b, a)| {
+                        assert_eq!(*a + *b, 100)
+                    })
+                    .unwrap();
+                }
+            });
+        });
+        assert_eq!(a.read(|a| *a).unwrap() + b.read(|b| *b).unwrap(), 100);
+
+        let same = a.clone();
+        assert!(matches!(
+            transaction((&a, &same), |_| ()),
+            Err(StateError::Duplicate)
+        ));
+
+        let held = b.clone();
+        std::thread::scope(|scope| {
+            let (sx, rx) = std::sync::mpsc::channel();
+            let (done_sx, done_rx) = std::sync::mpsc::channel::<()>();
+            scope.spawn(move || {
+                held.write(|_| {
+                    sx.send(()).unwrap();
+                    done_rx.recv().ok();
+                })
+                .unwrap();
+            });
+            rx.recv().unwrap();
+            let ret = try_transaction((&a, &b), Duration::from_millis(20), |_| ());
+            assert!(matches!(ret, Err(StateError::Timeout)));
+            done_sx.send(()).unwrap();
+        });
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_transaction_async() {
+        use core::future::{poll_fn, ready};
+        use r#async::block_on;
+        use std::{
+            task::Poll,
+            time::{Duration, Instant},
+        };
+
+        let sleep = |duration: Duration| {
+            let deadline = Instant::now() + duration;
+            poll_fn(move |cx| {
+                if Instant::now() >= deadline {
+                    return Poll::Ready(());
+                }
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })
+        };
+
+        let a = AsyncMutexState::new(100);
+        let b = AsyncMutexState::new(0);
+
+        // Opposite orders would deadlock if the locks were taken in argument order
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                block_on(async {
+                    for _ in 0..10000 {
+                        transaction_async((&a, &b), |(a, b)| {
+                            *a -= 1;
+                            *b += 1;
+                        })
+                        .await
+                        .unwrap();
+                    }
+                })
+            });
+            scope.spawn(|| {
+                block_on(async {
+                    for _ in 0..10000 {
+                        transaction_async((&b, &a), |(b, a)| {
+                            *b -= 1;
+                            *a += 1;
+                        })
+                        .await
+                        .unwrap();
+                    }
+                })
+            });
+            scope.spawn(|| {
+                block_on(async {
+                    for _ in 0..1000 {
+                        try_transaction_async((&b, &a), sleep(Duration::from_secs(5)), |(b, a)| {
+                            assert_eq!(*a + *b, 100)
+                        })
+                        .await
+                        .unwrap();
+                    }
+                })
+            });
+        });
+        let sum = block_on(async {
+            a.read(|a| ready(*a)).await.unwrap() + b.read(|b| ready(*b)).await.unwrap()
+        });
+        assert_eq!(sum, 100);
+
+        let ret = block_on(transaction_async((&a, &a), |_| ()));
+        assert!(matches!(ret, Err(StateError::Duplicate)));
+
+        std::thread::scope(|scope| {
+            let (sx, rx) = std::sync::mpsc::channel();
+            let (done_sx, done_rx) = std::sync::mpsc::channel::<()>();
+            let held = &b;
+            scope.spawn(move || {
+                block_on(held.write(move |_| {
+                    sx.send(()).unwrap();
+                    done_rx.recv().ok();
+                    ready(())
+                }))
+                .unwrap();
+            });
+            rx.recv().unwrap();
+            let ret = block_on(try_transaction_async(
+                (&a, &b),
+                sleep(Duration::from_millis(20)),
+                |_| (),
+            ));
+            assert!(matches!(ret, Err(StateError::Timeout)));
+            done_sx.send(()).unwrap();
+        });
+
+        // The locks taken before the timeout were released
+        let ret = block_on(transaction_async((&a, &b), |(a, b)| *a + *b));
+        assert_eq!(ret.unwrap(), 100);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_persisted() {
+        let dir = std::env::temp_dir().join(format!("state-persist-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+
+        // Nothing saved yet
+        let state = Persisted::load(&path, Json, || State::new(vec![1])).unwrap();
+        assert!(!path.exists());
+        state.write(|value| value.push(2)).unwrap();
+        assert!(state.is_dirty());
+        assert!(!path.exists());
+
+        // Saved when the last handle goes away
+        drop(state);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[1,2]");
+
+        let state: Persisted<State<Vec<i32>>, _> =
+            Persisted::load(&path, Json, || unreachable!()).unwrap();
+        let state = state.save_on_write();
+        assert_eq!(state.read(|value| value.clone()).unwrap(), vec![1, 2]);
+        state.write(|value| value.push(3)).unwrap();
+        assert!(!state.is_dirty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[1,2,3]");
+        assert!(state.take_error().is_none());
+        drop(state);
+
+        // No temporary files are left behind
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 1);
+
+        std::fs::write(&path, "not json").unwrap();
+        let corrupt = Persisted::<State<Vec<i32>>, _>::load(&path, Json, || State::new(vec![]));
+        assert_eq!(
+            corrupt.err().map(|err| err.kind()),
+            Some(std::io::ErrorKind::InvalidData)
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_history() {
+        struct Add(i32);
+
+        impl Patch<i32> for Add {
+            fn apply(&self, value: &mut i32) {
+                *value += self.0;
+            }
+
+            fn revert(&self, value: &mut i32) {
+                *value -= self.0;
+            }
+        }
+
+        let state = History::new(State::new(0), 3);
+        assert!(!state.can_undo());
+        assert!(!state.undo().unwrap());
+
+        state.write(|value| *value = 1).unwrap();
+        state.write(|value| *value = 2).unwrap();
+        state.apply(Add(10)).unwrap();
+        assert_eq!(state.read(|value| *value).unwrap(), 12);
+
+        assert!(state.undo().unwrap());
+        assert_eq!(state.read(|value| *value).unwrap(), 2);
+        assert!(state.undo().unwrap());
+        assert_eq!(state.read(|value| *value).unwrap(), 1);
+        assert!(state.can_redo());
+
+        assert!(state.redo().unwrap());
+        assert!(state.redo().unwrap());
+        assert_eq!(state.read(|value| *value).unwrap(), 12);
+        assert!(!state.redo().unwrap());
+
+        // Only the last three changes are kept
+        state.write(|value| *value = 13).unwrap();
+        for expected in [12, 2, 1] {
+            assert!(state.undo().unwrap());
+            assert_eq!(state.read(|value| *value).unwrap(), expected);
+        }
+        assert!(!state.undo().unwrap());
+        assert_eq!(state.read(|value| *value).unwrap(), 1);
+
+        // A new write drops the undone changes
+        state.redo().unwrap();
+        state.write(|value| *value = 20).unwrap();
+        assert!(!state.can_redo());
+        assert!(!state.redo().unwrap());
+        assert!(state.undo().unwrap());
+        assert_eq!(state.read(|value| *value).unwrap(), 2);
+
+        state.clear();
+        assert!(!state.can_undo());
+        assert!(!state.can_redo());
+    }
+
+    #[test]
+    fn test_history_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let state = History::new(State::new(1), 4);
+        state.write(|value| *value = 2).unwrap();
+
+        let failed = catch_unwind(AssertUnwindSafe(|| {
+            state.write(|_| panic!("write failed")).ok();
+        }));
+        assert!(failed.is_err());
+        assert!(state.can_undo());
+
+        state.write(|value| *value = 3).unwrap();
+        assert!(state.undo().unwrap());
+        assert!(state.undo().unwrap());
+        assert_eq!(state.read(|value| *value).unwrap(), 1);
+    }
+}
@@ -0,0 +1,479 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll, Waker},
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{IntoInner, StateError, StateTrait};
+
+pub type ObserverId = usize;
+
+type Callback<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+type DiffCallback<T> = Arc<dyn Fn(&T, &T) + Send + Sync>;
+
+type Snapshot<T> = fn(&T) -> T;
+
+struct Observers<T> {
+    next: ObserverId,
+    callbacks: Vec<(ObserverId, Callback<T>)>,
+    diffs: Vec<(ObserverId, DiffCallback<T>)>,
+    // Set while there are observers, so values are only copied when someone needs them
+    snapshot: Option<Snapshot<T>>,
+    wakers: Vec<Waker>,
+}
+
+// A change waiting for its observers, with copies taken while the state was locked
+struct Change<T> {
+    callbacks: Vec<Callback<T>>,
+    diffs: Vec<DiffCallback<T>>,
+    old: Option<T>,
+    new: T,
+}
+
+impl<T> Change<T> {
+    fn call(&self) {
+        for next in &self.callbacks {
+            next(&self.new);
+        }
+        if let Some(old) = &self.old {
+            for next in &self.diffs {
+                next(old, &self.new);
+            }
+        }
+    }
+}
+
+struct Changes<T> {
+    queue: VecDeque<Change<T>>,
+    delivering: bool,
+}
+
+struct Shared<T> {
+    observers: Mutex<Observers<T>>,
+    changes: Mutex<Changes<T>>,
+    version: AtomicU64,
+}
+
+// Lets the next change deliver the queue if an observer panicked
+struct Delivering<'a, T>(&'a Shared<T>);
+
+impl<'a, T> Drop for Delivering<'a, T> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            if let Ok(mut changes) = self.0.changes.lock() {
+                changes.delivering = false;
+            }
+        }
+    }
+}
+
+/// A state wrapper notifying its observers after every successful `write` or `replace_inner`.
+///
+/// The old and new value are copied while the state is locked, and the observers
+/// are called with them once it is unlocked, one change at a time and in the order
+/// of the writes. If observers are being called already, the change is queued and
+/// the write returns right away, so observers may read and write the state themselves.
+pub struct Observed<S, T> {
+    inner: S,
+    shared: Arc<Shared<T>>,
+}
+
+impl<S: Clone, T> Clone for Observed<S, T> {
+    fn clone(&self) -> Self {
+        Observed {
+            inner: self.inner.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<S, T> Observed<S, T> {
+    pub fn new(inner: S) -> Observed<S, T> {
+        Observed {
+            inner,
+            shared: Arc::new(Shared {
+                observers: Mutex::new(Observers {
+                    next: 0,
+                    callbacks: Vec::new(),
+                    diffs: Vec::new(),
+                    snapshot: None,
+                    wakers: Vec::new(),
+                }),
+                changes: Mutex::new(Changes {
+                    queue: VecDeque::new(),
+                    delivering: false,
+                }),
+                version: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The number of changes so far.
+    pub fn version(&self) -> u64 {
+        self.shared.version.load(Ordering::Acquire)
+    }
+
+    /// Call `func` with the new value after every change.
+    pub fn subscribe<F>(&self, func: F) -> ObserverId
+    where
+        T: Clone,
+        F: Fn(&T) + Send + Sync + 'static,
+    {
+        let mut observers = self.shared.observers.lock().expect("lock");
+        let id = observers.next;
+        observers.next += 1;
+        observers.callbacks.push((id, Arc::new(func)));
+        observers.snapshot = Some(T::clone);
+        id
+    }
+
+    /// Call `func` with the old and the new value after every change.
+    pub fn subscribe_diff<F>(&self, func: F) -> ObserverId
+    where
+        T: Clone,
+        F: Fn(&T, &T) + Send + Sync + 'static,
+    {
+        let mut observers = self.shared.observers.lock().expect("lock");
+        let id = observers.next;
+        observers.next += 1;
+        observers.diffs.push((id, Arc::new(func)));
+        observers.snapshot = Some(T::clone);
+        id
+    }
+
+    pub fn unsubscribe(&self, id: ObserverId) -> bool {
+        let mut observers = self.shared.observers.lock().expect("lock");
+        let len = observers.callbacks.len() + observers.diffs.len();
+        observers.callbacks.retain(|(next, _)| *next != id);
+        observers.diffs.retain(|(next, _)| *next != id);
+        if observers.callbacks.is_empty() && observers.diffs.is_empty() {
+            observers.snapshot = None;
+        }
+        len != observers.callbacks.len() + observers.diffs.len()
+    }
+
+    /// Wait for changes from async code.
+    pub fn watch(&self) -> Watcher<S, T>
+    where
+        S: Clone,
+    {
+        Watcher {
+            seen: self.version(),
+            state: self.clone(),
+        }
+    }
+
+    // How to copy the old and the new value, if the observers need them
+    fn snapshots(&self) -> (Option<Snapshot<T>>, Option<Snapshot<T>>) {
+        let observers = self.shared.observers.lock().expect("lock");
+        let old = observers.snapshot.filter(|_| !observers.diffs.is_empty());
+        (old, observers.snapshot)
+    }
+
+    // Bumps the version, wakes the watchers and queues the change for the observers.
+    // Called while the state is locked, so changes are queued in the order they were made.
+    fn changed(&self, old: Option<T>, new: Option<T>) {
+        let (change, wakers) = {
+            let mut observers = self.shared.observers.lock().expect("lock");
+            self.shared.version.fetch_add(1, Ordering::AcqRel);
+            // Observers added since the copies were made have to wait for the next change
+            let change = new.map(|new| Change {
+                callbacks: observers
+                    .callbacks
+                    .iter()
+                    .map(|(_, next)| next.clone())
+                    .collect(),
+                diffs: observers
+                    .diffs
+                    .iter()
+                    .map(|(_, next)| next.clone())
+                    .collect(),
+                old,
+                new,
+            });
+            (change, core::mem::take(&mut observers.wakers))
+        };
+
+        for waker in wakers {
+            waker.wake();
+        }
+
+        if let Some(change) = change {
+            self.shared
+                .changes
+                .lock()
+                .expect("lock")
+                .queue
+                .push_back(change);
+        }
+    }
+
+    // Calls the observers with the queued changes, unless that is already being done
+    fn deliver(&self) {
+        {
+            let mut changes = self.shared.changes.lock().expect("lock");
+            if changes.delivering || changes.queue.is_empty() {
+                return;
+            }
+            changes.delivering = true;
+        }
+
+        let _delivering = Delivering(&self.shared);
+        loop {
+            let change = {
+                let mut changes = self.shared.changes.lock().expect("lock");
+                match changes.queue.pop_front() {
+                    Some(change) => change,
+                    // Cleared under the same lock, so no change is left behind
+                    None => {
+                        changes.delivering = false;
+                        return;
+                    }
+                }
+            };
+            change.call();
+        }
+    }
+}
+
+impl<S, T> StateTrait<T> for Observed<S, T>
+where
+    S: StateTrait<T>,
+{
+    fn read<F, U>(&self, func: F) -> Result<U, StateError>
+    where
+        F: FnOnce(&T) -> U,
+    {
+        self.inner.read(func)
+    }
+
+    fn write<F, U>(&self, func: F) -> Result<U, StateError>
+    where
+        F: FnOnce(&mut T) -> U,
+    {
+        let (old, new) = self.snapshots();
+        let ret = self.inner.write(|value| {
+            let old = old.map(|snapshot| snapshot(value));
+            let ret = func(value);
+            self.changed(old, new.map(|snapshot| snapshot(value)));
+            ret
+        })?;
+        self.deliver();
+        Ok(ret)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+    }
+}
+
+impl<S, T> IntoInner<T> for Observed<S, T>
+where
+    S: StateTrait<T> + IntoInner<T>,
+{
+    /// Watchers are woken, but the observers aren't called as there is no new value.
+    fn into_inner(self) -> Option<T> {
+        let Observed { inner, shared } = self;
+        let ret = inner.into_inner();
+        Observed::<(), T> { inner: (), shared }.changed(None, None);
+        ret
+    }
+
+    fn replace_inner(&self, other: T) -> Option<T> {
+        let (snapshot, new) = self.snapshots();
+        let mut copy = new.map(|snapshot| snapshot(&other));
+        let mut other = Some(other);
+
+        // Replaced through a write, so the change is queued while the state is locked
+        let old = match self.inner.write(|value| {
+            let old = core::mem::replace(value, other.take().expect("value"));
+            self.changed(snapshot.map(|snapshot| snapshot(&old)), copy.take());
+            old
+        }) {
+            Ok(old) => Some(old),
+            // The state is empty, so there is no write to race with
+            Err(_) => {
+                let old = self.inner.replace_inner(other.take().expect("value"));
+                let copy_old = snapshot
+                    .zip(old.as_ref())
+                    .map(|(snapshot, old)| snapshot(old));
+                self.changed(copy_old, copy);
+                old
+            }
+        };
+        self.deliver();
+        old
+    }
+}
+
+/// Waits for changes of an [`Observed`] state, similar to `tokio::sync::watch::Receiver`.
+pub struct Watcher<S, T> {
+    state: Observed<S, T>,
+    seen: u64,
+}
+
+impl<S: Clone, T> Clone for Watcher<S, T> {
+    fn clone(&self) -> Self {
+        Watcher {
+            state: self.state.clone(),
+            seen: self.seen,
+        }
+    }
+}
+
+impl<S, T> Watcher<S, T> {
+    pub fn state(&self) -> &Observed<S, T> {
+        &self.state
+    }
+
+    /// Whether the state changed since the last [`changed`](Watcher::changed) or
+    /// [`mark_seen`](Watcher::mark_seen).
+    pub fn has_changed(&self) -> bool {
+        self.state.version() != self.seen
+    }
+
+    pub fn mark_seen(&mut self) {
+        self.seen = self.state.version();
+    }
+
+    /// Wait until the state changed. Resolves right away if it already has.
+    /// Several changes in a row are seen as one.
+    pub fn changed(&mut self) -> Changed<'_, S, T> {
+        Changed { watcher: self }
+    }
+}
+
+pub struct Changed<'a, S, T> {
+    watcher: &'a mut Watcher<S, T>,
+}
+
+impl<'a, S, T> Future for Changed<'a, S, T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let watcher = &mut *self.watcher;
+        let shared = &watcher.state.shared;
+
+        if watcher.has_changed() {
+            watcher.mark_seen();
+            return Poll::Ready(());
+        }
+
+        let mut observers = shared.observers.lock().expect("lock");
+        // Changes bump the version under the same lock, so none can slip in between
+        if watcher.has_changed() {
+            drop(observers);
+            watcher.mark_seen();
+            return Poll::Ready(());
+        }
+        if !observers
+            .wakers
+            .iter()
+            .any(|next| next.will_wake(cx.waker()))
+        {
+            observers.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "async")]
+mod r#async {
+    use async_trait::async_trait;
+    use core::future::Future;
+
+    use super::Observed;
+    use crate::{AsyncIntoInner, AsyncStateTrait, StateError};
+
+    #[async_trait]
+    impl<S, T> AsyncStateTrait<T> for Observed<S, T>
+    where
+        S: AsyncStateTrait<T> + Send + Sync,
+        T: Send + Sync,
+    {
+        async fn read<F, U>(&self, func: F) -> Result<U::Output, StateError>
+        where
+            F: FnOnce(&T) -> U + Send,
+            U: Future + Send,
+        {
+            self.inner.read(func).await
+        }
+
+        async fn write<F, U>(&self, mut func: F) -> Result<U::Output, StateError>
+        where
+            F: FnMut(&mut T) -> U + Send,
+            U: Future + Send,
+        {
+            let (old, new) = self.snapshots();
+            // `func` is done with the value once it returns the future,
+            // so the new value is copied right away instead of after awaiting it
+            let ret = self
+                .inner
+                .write(|value| {
+                    let old = old.map(|snapshot| snapshot(value));
+                    let ret = func(value);
+                    self.changed(old, new.map(|snapshot| snapshot(value)));
+                    ret
+                })
+                .await?;
+            self.deliver();
+            Ok(ret)
+        }
+
+        async fn is_valid(&self) -> bool {
+            self.inner.is_valid().await
+        }
+    }
+
+    #[async_trait]
+    impl<S, T> AsyncIntoInner<T> for Observed<S, T>
+    where
+        S: AsyncStateTrait<T> + AsyncIntoInner<T> + Send + Sync,
+        T: Send + Sync,
+    {
+        async fn into_inner(self) -> Option<T> {
+            let Observed { inner, shared } = self;
+            let ret = inner.into_inner().await;
+            Observed::<(), T> { inner: (), shared }.changed(None, None);
+            ret
+        }
+
+        async fn replace_inner(&self, other: T) -> Option<T> {
+            let (snapshot, new) = self.snapshots();
+            let mut copy = new.map(|snapshot| snapshot(&other));
+            let mut other = Some(other);
+
+            let old = match self
+                .inner
+                .write(|value| {
+                    let old = core::mem::replace(value, other.take().expect("value"));
+                    self.changed(snapshot.map(|snapshot| snapshot(&old)), copy.take());
+                    core::future::ready(old)
+                })
+                .await
+            {
+                Ok(old) => Some(old),
+                Err(_) => {
+                    let old = self.inner.replace_inner(other.take().expect("value")).await;
+                    let copy_old = snapshot
+                        .zip(old.as_ref())
+                        .map(|(snapshot, old)| snapshot(old));
+                    self.changed(copy_old, copy);
+                    old
+                }
+            };
+            self.deliver();
+            old
+        }
+    }
+}
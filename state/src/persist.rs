@@ -0,0 +1,303 @@
+use core::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+    thread,
+};
+
+use crate::{StateError, StateTrait};
+
+/// How a [`Persisted`] state is written to disk.
+pub trait Format {
+    fn encode<T: Serialize>(&self, value: &T) -> io::Result<Vec<u8>>;
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> io::Result<T>;
+}
+
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Format for Bincode {
+    fn encode<T: Serialize>(&self, value: &T) -> io::Result<Vec<u8>> {
+        bincode::serialize(value).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> io::Result<T> {
+        bincode::deserialize(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl Format for Json {
+    fn encode<T: Serialize>(&self, value: &T) -> io::Result<Vec<u8>> {
+        serde_json::to_vec(value).map_err(io::Error::from)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> io::Result<T> {
+        serde_json::from_slice(bytes).map_err(io::Error::from)
+    }
+}
+
+// The snapshot is written next to the file and renamed over it, so a crash never leaves half of it.
+// Temporary files are unique, as other handles or processes may save to the same path.
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = PathBuf::from(tmp);
+
+    let written = File::create(&tmp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+    if let Err(err) = written.and_then(|_| fs::rename(&tmp, path)) {
+        fs::remove_file(&tmp).ok();
+        return Err(err);
+    }
+
+    // The rename is only durable once the directory is synced as well
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+struct Shared<S, F> {
+    state: S,
+    format: F,
+    path: PathBuf,
+    dirty: AtomicBool,
+    on_write: AtomicBool,
+    saving: Mutex<()>,
+    error: Mutex<Option<io::Error>>,
+    // Kept so saving on write and on drop doesn't need the serde bounds
+    save: fn(&Shared<S, F>) -> io::Result<()>,
+}
+
+impl<S: Serialize, F: Format> Shared<S, F> {
+    fn save(&self) -> io::Result<()> {
+        let _saving = self.saving.lock().expect("lock");
+        // Cleared first, so a write during the save marks it dirty again
+        self.dirty.store(false, Ordering::Release);
+        let ret = self
+            .format
+            .encode(&self.state)
+            .and_then(|bytes| write_atomic(&self.path, &bytes));
+        if ret.is_err() {
+            self.dirty.store(true, Ordering::Release);
+        }
+        ret
+    }
+}
+
+impl<S, F> Shared<S, F> {
+    fn save_dirty(&self) {
+        if self.dirty.load(Ordering::Acquire) {
+            if let Err(err) = (self.save)(self) {
+                *self.error.lock().expect("lock") = Some(err);
+            }
+        }
+    }
+
+    fn changed(&self) {
+        self.dirty.store(true, Ordering::Release);
+        if self.on_write.load(Ordering::Acquire) {
+            self.save_dirty();
+        }
+    }
+}
+
+impl<S, F> Drop for Shared<S, F> {
+    fn drop(&mut self) {
+        if self.dirty.load(Ordering::Acquire) {
+            (self.save)(self).ok();
+        }
+    }
+}
+
+/// A state wrapper keeping a snapshot of the state on disk.
+///
+/// By default the snapshot is only written by [`save`](Persisted::save),
+/// and when the last handle is dropped with unsaved changes.
+/// Writes go to a temporary file first, which is then renamed over the snapshot.
+pub struct Persisted<S, F> {
+    shared: Arc<Shared<S, F>>,
+}
+
+impl<S, F> Clone for Persisted<S, F> {
+    fn clone(&self) -> Self {
+        Persisted {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<S, F> Persisted<S, F>
+where
+    S: Serialize + DeserializeOwned,
+    F: Format,
+{
+    /// Load the snapshot at `path`, or start from `default` if there is none yet.
+    pub fn load<P, D>(path: P, format: F, default: D) -> io::Result<Persisted<S, F>>
+    where
+        P: Into<PathBuf>,
+        D: FnOnce() -> S,
+    {
+        let path = path.into();
+        let state = match fs::read(&path) {
+            Ok(bytes) => format.decode(&bytes)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => default(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Persisted {
+            shared: Arc::new(Shared {
+                state,
+                format,
+                path,
+                dirty: AtomicBool::new(false),
+                on_write: AtomicBool::new(false),
+                saving: Mutex::new(()),
+                error: Mutex::new(None),
+                save: Shared::save,
+            }),
+        })
+    }
+
+    /// Write the snapshot now.
+    pub fn save(&self) -> io::Result<()> {
+        self.shared.save()
+    }
+}
+
+impl<S, F> Persisted<S, F> {
+    /// Save after every write, on the writing thread.
+    pub fn save_on_write(self) -> Self {
+        self.shared.on_write.store(true, Ordering::Release);
+        self
+    }
+
+    /// Save every `interval` if the state changed, from a background thread
+    /// which stops once every handle was dropped.
+    pub fn save_every(self, interval: Duration) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Send + Sync + 'static,
+    {
+        let shared: Weak<Shared<S, F>> = Arc::downgrade(&self.shared);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match shared.upgrade() {
+                Some(shared) => shared.save_dirty(),
+                None => break,
+            }
+        });
+        self
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.shared.state
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.shared.path
+    }
+
+    /// Whether the state changed since it was last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.shared.dirty.load(Ordering::Acquire)
+    }
+
+    /// The error of the last failed save made on write or in the background.
+    pub fn take_error(&self) -> Option<io::Error> {
+        self.shared.error.lock().expect("lock").take()
+    }
+}
+
+impl<S, F, T> StateTrait<T> for Persisted<S, F>
+where
+    S: StateTrait<T>,
+{
+    fn read<R, U>(&self, func: R) -> Result<U, StateError>
+    where
+        R: FnOnce(&T) -> U,
+    {
+        self.shared.state.read(func)
+    }
+
+    fn write<W, U>(&self, func: W) -> Result<U, StateError>
+    where
+        W: FnOnce(&mut T) -> U,
+    {
+        let ret = self.shared.state.write(func)?;
+        self.shared.changed();
+        Ok(ret)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.shared.state.is_valid()
+    }
+}
+
+#[cfg(feature = "async")]
+mod r#async {
+    use async_trait::async_trait;
+    use core::future::Future;
+
+    use super::Persisted;
+    use crate::{AsyncStateTrait, StateError};
+
+    /// Saving on write blocks the writing task while the snapshot is written.
+    #[async_trait]
+    impl<S, F, T> AsyncStateTrait<T> for Persisted<S, F>
+    where
+        S: AsyncStateTrait<T> + Send + Sync,
+        F: Send + Sync,
+        T: Send + Sync,
+    {
+        async fn read<R, U>(&self, func: R) -> Result<U::Output, StateError>
+        where
+            R: FnOnce(&T) -> U + Send,
+            U: Future + Send,
+        {
+            self.shared.state.read(func).await
+        }
+
+        async fn write<W, U>(&self, func: W) -> Result<U::Output, StateError>
+        where
+            W: FnMut(&mut T) -> U + Send,
+            U: Future + Send,
+        {
+            let ret = self.shared.state.write(func).await?;
+            self.shared.changed();
+            Ok(ret)
+        }
+
+        async fn is_valid(&self) -> bool {
+            self.shared.state.is_valid().await
+        }
+    }
+}
@@ -6,6 +6,9 @@ use std::rc::{Rc, Weak};
 
 use crate::{Downgrade, IntoInner, StateError};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 pub trait StateTrait<T> {
     fn read<F, U>(&self, func: F) -> Result<U, StateError>
     where
@@ -81,6 +84,22 @@ impl<T> IntoInner<T> for State<T> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for State<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.borrow().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for State<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(State {
+            inner: Rc::new(RefCell::new(Option::deserialize(deserializer)?)),
+        })
+    }
+}
+
 impl<T> Downgrade for State<T> {
     type Output = WeakState<T>;
 
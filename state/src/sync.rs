@@ -14,6 +14,9 @@ use std::sync::{Mutex, RwLock};
 #[cfg(not(feature = "std"))]
 use spin::{Mutex, RwLock};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 pub type MutexState<T> = LockState<T, SendLock<Mutex<Option<T>>>>;
 
 pub type WeakMutexState<T> = WeakLockState<T, WeakSendLock<Mutex<Option<T>>>>;
@@ -26,7 +29,7 @@ pub struct LockState<T, L>
 where
     for<'a> L: Lock<'a, Option<T>>,
 {
-    inner: L,
+    pub(crate) inner: L,
     _t: PhantomData<T>,
 }
 
@@ -84,7 +87,7 @@ where
             None => return Err(StateError::Empty),
         };
 
-        Ok(func(&ret))
+        Ok(func(ret))
     }
 
     fn write<F, U>(&self, func: F) -> Result<U, StateError>
@@ -118,6 +121,31 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+impl<T, L> Serialize for LockState<T, L>
+where
+    for<'a> L: Lock<'a, Option<T>>,
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.read().get().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, L> Deserialize<'de> for LockState<T, L>
+where
+    for<'a> L: Lock<'a, Option<T>>,
+    T: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(LockState {
+            inner: L::new(Option::deserialize(deserializer)?),
+            _t: PhantomData,
+        })
+    }
+}
+
 impl<T, L> Downgrade for LockState<T, L>
 where
     L: lock::Downgrade,
@@ -0,0 +1,211 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use locking::{async_lock::AsyncLock, lock::Address, AsyncLockApi, LockApiReadWriteGuard};
+
+use super::order;
+use crate::{AsyncLockState, StateError};
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A state which can take part in a [`transaction_async`].
+pub trait AsyncTransactionState: Send + Sync {
+    type Value;
+
+    type Guard<'g>: Send
+    where
+        Self: 'g;
+
+    /// States sharing a lock have the same address.
+    fn address(&self) -> usize;
+
+    fn lock(&self) -> BoxFuture<'_, Self::Guard<'_>>;
+
+    fn value<'b, 'g>(guard: &'b mut Self::Guard<'g>) -> Option<&'b mut Self::Value>
+    where
+        Self: 'g;
+}
+
+impl<T, L> AsyncTransactionState for AsyncLockState<T, L>
+where
+    for<'a> L: AsyncLock<'a, Option<T>>,
+    L: Address + Send + Sync,
+    T: Send + Sync,
+{
+    type Value = T;
+
+    type Guard<'g>
+        = <L as AsyncLockApi<'g, Option<T>>>::ReadWriteGuard
+    where
+        Self: 'g;
+
+    fn address(&self) -> usize {
+        self.lock.address()
+    }
+
+    fn lock(&self) -> BoxFuture<'_, Self::Guard<'_>> {
+        self.lock.write()
+    }
+
+    fn value<'b, 'g>(guard: &'b mut Self::Guard<'g>) -> Option<&'b mut Self::Value>
+    where
+        Self: 'g,
+    {
+        guard.get_mut().as_mut()
+    }
+}
+
+impl<S: AsyncTransactionState> AsyncTransactionState for &S {
+    type Value = S::Value;
+
+    type Guard<'g>
+        = S::Guard<'g>
+    where
+        Self: 'g;
+
+    fn address(&self) -> usize {
+        (**self).address()
+    }
+
+    fn lock(&self) -> BoxFuture<'_, Self::Guard<'_>> {
+        (**self).lock()
+    }
+
+    fn value<'b, 'g>(guard: &'b mut Self::Guard<'g>) -> Option<&'b mut Self::Value>
+    where
+        Self: 'g,
+    {
+        S::value(guard)
+    }
+}
+
+/// A tuple of async states written together by a [`transaction_async`], calling `F`
+/// with a tuple of mutable references to their values.
+pub trait AsyncTransact<F, U> {
+    fn transact_async<'a>(&'a self, func: F) -> BoxFuture<'a, Result<U, StateError>>
+    where
+        F: 'a,
+        U: 'a;
+}
+
+impl<A, B, F, U> AsyncTransact<F, U> for (A, B)
+where
+    A: AsyncTransactionState,
+    B: AsyncTransactionState,
+    F: FnOnce((&mut A::Value, &mut B::Value)) -> U + Send,
+    U: Send,
+{
+    fn transact_async<'a>(&'a self, func: F) -> BoxFuture<'a, Result<U, StateError>>
+    where
+        F: 'a,
+        U: 'a,
+    {
+        Box::pin(async move {
+            let (a, b) = self;
+            let (mut ga, mut gb) = (None, None);
+            for i in order([a.address(), b.address()])? {
+                match i {
+                    0 => ga = Some(a.lock().await),
+                    _ => gb = Some(b.lock().await),
+                }
+            }
+
+            match (
+                ga.as_mut().and_then(A::value),
+                gb.as_mut().and_then(B::value),
+            ) {
+                (Some(a), Some(b)) => Ok(func((a, b))),
+                _ => Err(StateError::Empty),
+            }
+        })
+    }
+}
+
+impl<A, B, C, F, U> AsyncTransact<F, U> for (A, B, C)
+where
+    A: AsyncTransactionState,
+    B: AsyncTransactionState,
+    C: AsyncTransactionState,
+    F: FnOnce((&mut A::Value, &mut B::Value, &mut C::Value)) -> U + Send,
+    U: Send,
+{
+    fn transact_async<'a>(&'a self, func: F) -> BoxFuture<'a, Result<U, StateError>>
+    where
+        F: 'a,
+        U: 'a,
+    {
+        Box::pin(async move {
+            let (a, b, c) = self;
+            let (mut ga, mut gb, mut gc) = (None, None, None);
+            for i in order([a.address(), b.address(), c.address()])? {
+                match i {
+                    0 => ga = Some(a.lock().await),
+                    1 => gb = Some(b.lock().await),
+                    _ => gc = Some(c.lock().await),
+                }
+            }
+
+            match (
+                ga.as_mut().and_then(A::value),
+                gb.as_mut().and_then(B::value),
+                gc.as_mut().and_then(C::value),
+            ) {
+                (Some(a), Some(b), Some(c)) => Ok(func((a, b, c))),
+                _ => Err(StateError::Empty),
+            }
+        })
+    }
+}
+
+/// Write several async states at once, like [`transaction`](crate::transaction).
+pub async fn transaction_async<S, F, U>(states: S, func: F) -> Result<U, StateError>
+where
+    S: AsyncTransact<F, U>,
+{
+    states.transact_async(func).await
+}
+
+/// Like [`transaction_async`], but fails with [`StateError::Timeout`] if `deadline`
+/// completes before the locks could all be taken, eg. `tokio::time::sleep(timeout)`.
+/// The locks taken so far are released.
+pub async fn try_transaction_async<S, D, F, U>(
+    states: S,
+    deadline: D,
+    func: F,
+) -> Result<U, StateError>
+where
+    S: AsyncTransact<F, U>,
+    D: Future,
+{
+    Deadline {
+        future: states.transact_async(func),
+        deadline: Box::pin(deadline),
+    }
+    .await
+}
+
+struct Deadline<F, D> {
+    future: F,
+    deadline: Pin<Box<D>>,
+}
+
+impl<F, D, U> Future for Deadline<F, D>
+where
+    F: Future<Output = Result<U, StateError>> + Unpin,
+    D: Future,
+{
+    type Output = Result<U, StateError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(ret) = Pin::new(&mut self.future).poll(cx) {
+            return Poll::Ready(ret);
+        }
+
+        match self.deadline.as_mut().poll(cx) {
+            Poll::Ready(_) => Poll::Ready(Err(StateError::Timeout)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+#[cfg(feature = "async")]
+mod r#async;
+#[cfg(feature = "sync")]
+mod sync;
+
+#[cfg(feature = "async")]
+pub use self::r#async::*;
+#[cfg(feature = "sync")]
+pub use self::sync::*;
+
+use crate::StateError;
+
+// The positions of the states, ordered by the address of their lock
+fn order<const N: usize>(addresses: [usize; N]) -> Result<[usize; N], StateError> {
+    let mut order = [0; N];
+    for (i, next) in order.iter_mut().enumerate() {
+        *next = i;
+    }
+    order.sort_unstable_by_key(|i| addresses[*i]);
+
+    // Locking the same state twice would deadlock
+    if order
+        .windows(2)
+        .any(|pair| addresses[pair[0]] == addresses[pair[1]])
+    {
+        return Err(StateError::Duplicate);
+    }
+
+    Ok(order)
+}
@@ -0,0 +1,281 @@
+#[cfg(feature = "std")]
+use core::time::Duration;
+use locking::{
+    lock::{Address, Lock},
+    LockApi, LockApiReadWriteGuard, TryLockApi,
+};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use super::order;
+use crate::{sync::LockState, StateError};
+
+/// A state which can take part in a [`transaction`].
+pub trait TransactionState {
+    type Value;
+
+    type Guard<'g>
+    where
+        Self: 'g;
+
+    /// States sharing a lock have the same address.
+    fn address(&self) -> usize;
+
+    fn lock(&self) -> Self::Guard<'_>;
+
+    fn try_lock(&self) -> Option<Self::Guard<'_>>;
+
+    fn value<'b, 'g>(guard: &'b mut Self::Guard<'g>) -> Option<&'b mut Self::Value>
+    where
+        Self: 'g;
+}
+
+impl<T, L> TransactionState for LockState<T, L>
+where
+    for<'a> L: Lock<'a, Option<T>> + TryLockApi<'a, Option<T>>,
+    L: Address,
+{
+    type Value = T;
+
+    type Guard<'g>
+        = <L as LockApi<'g, Option<T>>>::ReadWriteGuard
+    where
+        Self: 'g;
+
+    fn address(&self) -> usize {
+        self.inner.address()
+    }
+
+    fn lock(&self) -> Self::Guard<'_> {
+        self.inner.write()
+    }
+
+    fn try_lock(&self) -> Option<Self::Guard<'_>> {
+        self.inner.try_write()
+    }
+
+    fn value<'b, 'g>(guard: &'b mut Self::Guard<'g>) -> Option<&'b mut Self::Value>
+    where
+        Self: 'g,
+    {
+        guard.get_mut().as_mut()
+    }
+}
+
+impl<S: TransactionState> TransactionState for &S {
+    type Value = S::Value;
+
+    type Guard<'g>
+        = S::Guard<'g>
+    where
+        Self: 'g;
+
+    fn address(&self) -> usize {
+        (**self).address()
+    }
+
+    fn lock(&self) -> Self::Guard<'_> {
+        (**self).lock()
+    }
+
+    fn try_lock(&self) -> Option<Self::Guard<'_>> {
+        (**self).try_lock()
+    }
+
+    fn value<'b, 'g>(guard: &'b mut Self::Guard<'g>) -> Option<&'b mut Self::Value>
+    where
+        Self: 'g,
+    {
+        S::value(guard)
+    }
+}
+
+/// A tuple of states written together by a [`transaction`], calling `F`
+/// with a tuple of mutable references to their values.
+pub trait Transact<F, U> {
+    fn transact(&self, func: F) -> Result<U, StateError>;
+
+    #[cfg(feature = "std")]
+    fn try_transact(&self, timeout: Duration, func: F) -> Result<U, StateError>;
+}
+
+// Sleeps a little longer after every failed attempt, so waiting doesn't keep a core busy
+#[cfg(feature = "std")]
+struct Backoff {
+    deadline: Instant,
+    delay: Duration,
+}
+
+#[cfg(feature = "std")]
+impl Backoff {
+    const MAX: Duration = Duration::from_millis(1);
+
+    fn new(timeout: Duration) -> Backoff {
+        Backoff {
+            deadline: Instant::now() + timeout,
+            delay: Duration::from_micros(1),
+        }
+    }
+
+    fn wait(&mut self) -> Result<(), StateError> {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return Err(StateError::Timeout);
+        }
+        std::thread::sleep(self.delay.min(self.deadline - now));
+        self.delay = (self.delay * 2).min(Backoff::MAX);
+        Ok(())
+    }
+}
+
+impl<A, B, F, U> Transact<F, U> for (A, B)
+where
+    A: TransactionState,
+    B: TransactionState,
+    F: FnOnce((&mut A::Value, &mut B::Value)) -> U,
+{
+    fn transact(&self, func: F) -> Result<U, StateError> {
+        let (a, b) = self;
+        let (mut ga, mut gb) = (None, None);
+        for i in order([a.address(), b.address()])? {
+            match i {
+                0 => ga = Some(a.lock()),
+                _ => gb = Some(b.lock()),
+            }
+        }
+
+        match (
+            ga.as_mut().and_then(A::value),
+            gb.as_mut().and_then(B::value),
+        ) {
+            (Some(a), Some(b)) => Ok(func((a, b))),
+            _ => Err(StateError::Empty),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn try_transact(&self, timeout: Duration, func: F) -> Result<U, StateError> {
+        let (a, b) = self;
+        let order = order([a.address(), b.address()])?;
+        let mut backoff = Backoff::new(timeout);
+
+        loop {
+            let (mut ga, mut gb) = (None, None);
+            // Back off by releasing every lock as soon as one is taken
+            let locked = order.iter().all(|i| match i {
+                0 => {
+                    ga = a.try_lock();
+                    ga.is_some()
+                }
+                _ => {
+                    gb = b.try_lock();
+                    gb.is_some()
+                }
+            });
+
+            if locked {
+                return match (
+                    ga.as_mut().and_then(A::value),
+                    gb.as_mut().and_then(B::value),
+                ) {
+                    (Some(a), Some(b)) => Ok(func((a, b))),
+                    _ => Err(StateError::Empty),
+                };
+            }
+
+            drop((ga, gb));
+            backoff.wait()?;
+        }
+    }
+}
+
+impl<A, B, C, F, U> Transact<F, U> for (A, B, C)
+where
+    A: TransactionState,
+    B: TransactionState,
+    C: TransactionState,
+    F: FnOnce((&mut A::Value, &mut B::Value, &mut C::Value)) -> U,
+{
+    fn transact(&self, func: F) -> Result<U, StateError> {
+        let (a, b, c) = self;
+        let (mut ga, mut gb, mut gc) = (None, None, None);
+        for i in order([a.address(), b.address(), c.address()])? {
+            match i {
+                0 => ga = Some(a.lock()),
+                1 => gb = Some(b.lock()),
+                _ => gc = Some(c.lock()),
+            }
+        }
+
+        match (
+            ga.as_mut().and_then(A::value),
+            gb.as_mut().and_then(B::value),
+            gc.as_mut().and_then(C::value),
+        ) {
+            (Some(a), Some(b), Some(c)) => Ok(func((a, b, c))),
+            _ => Err(StateError::Empty),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn try_transact(&self, timeout: Duration, func: F) -> Result<U, StateError> {
+        let (a, b, c) = self;
+        let order = order([a.address(), b.address(), c.address()])?;
+        let mut backoff = Backoff::new(timeout);
+
+        loop {
+            let (mut ga, mut gb, mut gc) = (None, None, None);
+            let locked = order.iter().all(|i| match i {
+                0 => {
+                    ga = a.try_lock();
+                    ga.is_some()
+                }
+                1 => {
+                    gb = b.try_lock();
+                    gb.is_some()
+                }
+                _ => {
+                    gc = c.try_lock();
+                    gc.is_some()
+                }
+            });
+
+            if locked {
+                return match (
+                    ga.as_mut().and_then(A::value),
+                    gb.as_mut().and_then(B::value),
+                    gc.as_mut().and_then(C::value),
+                ) {
+                    (Some(a), Some(b), Some(c)) => Ok(func((a, b, c))),
+                    _ => Err(StateError::Empty),
+                };
+            }
+
+            drop((ga, gb, gc));
+            backoff.wait()?;
+        }
+    }
+}
+
+/// Write several states at once, eg. `transaction((&a, &b), |(a, b)| ...)`.
+///
+/// The locks are always taken in the order of their address,
+/// so transactions over the same states can't deadlock each other.
+/// Fails with [`StateError::Empty`] if one of the states is empty,
+/// and with [`StateError::Duplicate`] if the same state is passed twice.
+pub fn transaction<S, F, U>(states: S, func: F) -> Result<U, StateError>
+where
+    S: Transact<F, U>,
+{
+    states.transact(func)
+}
+
+/// Like [`transaction`], but fails with [`StateError::Timeout`]
+/// if the locks couldn't all be taken within `timeout`.
+#[cfg(feature = "std")]
+pub fn try_transaction<S, F, U>(states: S, timeout: Duration, func: F) -> Result<U, StateError>
+where
+    S: Transact<F, U>,
+{
+    states.try_transact(timeout, func)
+}